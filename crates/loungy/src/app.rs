@@ -10,9 +10,14 @@
  */
 use crate::ipc::server::PlatformListener;
 use crate::{
-    commands::RootCommands,
+    commands::{
+        RootCommands,
+        root::{frecency::Frecency, numbat::NumbatContext},
+    },
+    config::Config,
     hotkey::HotkeyManager,
     ipc::server::start_server,
+    paths::paths,
     theme::LTheme,
     window::{LWindow, LWindowStyle},
     workspace::Workspace,
@@ -20,6 +25,10 @@ use crate::{
 use gpui::{App, AppContext, Bounds, Pixels, Point, Size, WindowBackgroundAppearance};
 use loungy_assets::Assets;
 pub fn run_app(listener: PlatformListener, app: gpui::Application) {
+    // Warmed up in the background, so the first command needing it doesn't wait for the login shell
+    std::thread::spawn(|| {
+        paths().path_env();
+    });
     app.with_assets(Assets).run(move |cx: &mut App| {
         LTheme::init(cx);
         // TODO: This still only works for a single display
@@ -35,9 +44,12 @@ pub fn run_app(listener: PlatformListener, app: gpui::Application) {
             cx.background_spawn(WindowBackgroundAppearance::from(
                 theme.window_background.clone().unwrap_or_default(),
             ));
+            Frecency::init(cx);
+            NumbatContext::init(cx);
             RootCommands::init(window, cx);
             cx.spawn(|cx| start_server(listener, cx)).detach();
             HotkeyManager::init(cx);
+            Config::watch(cx);
             let view = Workspace::build(window, cx);
             LWindow::init(window, cx);
 
@@ -0,0 +1,225 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+// Export and import of everything stored in the database, to carry it over to another machine
+
+use std::{
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use bonsaidb::{core::schema::SerializedCollection, local::Database};
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::Value;
+
+use crate::{
+    commands::archived_collections,
+    db::db,
+    hotkey::{CommandAliases, CommandHotkeys},
+    paths::paths,
+};
+
+// Bump whenever the layout of the archive changes, older archives are rejected by newer versions and vice versa
+pub const ARCHIVE_VERSION: u32 = 1;
+
+// Keys of the `kv` database worth carrying over
+const KV_KEYS: [&str; 2] = ["preferences", "theme"];
+
+// A collection that is included in archives
+pub trait Archived: SerializedCollection<Contents = Self> + Serialize + DeserializeOwned {
+    // Called for archives without secrets, `None` leaves the document out entirely
+    fn without_secrets(self) -> Option<Self> {
+        Some(self)
+    }
+}
+
+pub struct ArchivedCollection {
+    name: String,
+    db: fn() -> &'static Database,
+    export: fn(&Database, bool) -> anyhow::Result<Vec<Value>>,
+    import: fn(&Database, Vec<Value>) -> anyhow::Result<usize>,
+}
+
+impl ArchivedCollection {
+    pub fn new<C: Archived + 'static>(db: fn() -> &'static Database) -> Self {
+        Self {
+            name: C::collection_name().to_string(),
+            db,
+            export: export_collection::<C>,
+            import: import_collection::<C>,
+        }
+    }
+}
+
+fn export_collection<C: Archived>(db: &Database, secrets: bool) -> anyhow::Result<Vec<Value>> {
+    C::all(db)
+        .query()?
+        .into_iter()
+        .filter_map(|doc| {
+            if secrets {
+                Some(doc.contents)
+            } else {
+                doc.contents.without_secrets()
+            }
+        })
+        .map(|contents| Ok(serde_json::to_value(contents)?))
+        .collect()
+}
+
+// Documents with the same id get replaced, everything else is kept
+fn import_collection<C: Archived>(db: &Database, docs: Vec<Value>) -> anyhow::Result<usize> {
+    let mut count = 0;
+    for doc in docs {
+        let contents: C = serde_json::from_value(doc)?;
+        match C::natural_id(&contents) {
+            Some(id) => {
+                C::overwrite(&id, contents, db)?;
+            }
+            None => {
+                C::push(contents, db)?;
+            }
+        }
+        count += 1;
+    }
+    Ok(count)
+}
+
+fn collections() -> Vec<ArchivedCollection> {
+    let mut collections = vec![
+        ArchivedCollection::new::<CommandHotkeys>(crate::hotkey::db),
+        ArchivedCollection::new::<CommandAliases>(crate::hotkey::db_aliases),
+    ];
+    collections.append(&mut archived_collections());
+    collections
+}
+
+// Cache folders holding files the archived documents point to, such as copied images
+fn file_dirs() -> Vec<PathBuf> {
+    vec![
+        #[cfg(feature = "clipboard")]
+        paths().cache.join("clipboard"),
+    ]
+}
+
+// File contents by their path relative to the cache folder
+fn export_files() -> anyhow::Result<HashMap<String, String>> {
+    let cache = &paths().cache;
+    let mut files = HashMap::new();
+    for dir in file_dirs() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(relative) = path.strip_prefix(cache) else {
+                continue;
+            };
+            if path.is_file() {
+                files.insert(
+                    relative.to_string_lossy().to_string(),
+                    STANDARD.encode(std::fs::read(&path)?),
+                );
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn import_files(files: &HashMap<String, String>) -> anyhow::Result<()> {
+    let cache = &paths().cache;
+    let dirs = file_dirs();
+    for (relative, contents) in files {
+        // Nothing may end up outside of the folders that are exported
+        let relative = Path::new(relative);
+        if !relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            continue;
+        }
+        let path = cache.join(relative);
+        let Some(dir) = path.parent().filter(|dir| dirs.iter().any(|d| d == dir)) else {
+            continue;
+        };
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(&path, STANDARD.decode(contents)?)?;
+    }
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Archive {
+    pub version: u32,
+    pub created: Timestamp,
+    pub secrets: bool,
+    pub kv: HashMap<String, Value>,
+    pub collections: HashMap<String, Vec<Value>>,
+    // Missing from archives written before files were included, which still import fine
+    #[serde(default)]
+    pub files: HashMap<String, String>,
+}
+
+impl Archive {
+    pub fn export(path: &Path, secrets: bool) -> anyhow::Result<usize> {
+        let kv = KV_KEYS
+            .iter()
+            .filter_map(|key| Some((key.to_string(), db().get::<Value>(key)?)))
+            .collect();
+        let mut collections = HashMap::new();
+        let mut count = 0;
+        for collection in self::collections() {
+            let docs = (collection.export)((collection.db)(), secrets)?;
+            count += docs.len();
+            collections.insert(collection.name, docs);
+        }
+        let archive = Archive {
+            version: ARCHIVE_VERSION,
+            created: Timestamp::now(),
+            secrets,
+            kv,
+            collections,
+            files: export_files()?,
+        };
+        std::fs::write(path, serde_json::to_vec_pretty(&archive)?)?;
+        #[cfg(unix)]
+        if secrets {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        }
+        Ok(count)
+    }
+    // Collections missing from the archive, or unknown to this build, are left alone
+    pub fn import(path: &Path) -> anyhow::Result<usize> {
+        let mut archive: Archive = serde_json::from_slice(&std::fs::read(path)?)?;
+        if archive.version != ARCHIVE_VERSION {
+            return Err(anyhow::anyhow!(
+                "Unsupported archive version {}, expected {}",
+                archive.version,
+                ARCHIVE_VERSION
+            ));
+        }
+        for (key, value) in &archive.kv {
+            if KV_KEYS.contains(&key.as_str()) {
+                db().set::<Value>(key, value)?;
+            }
+        }
+        let mut count = 0;
+        for collection in collections() {
+            if let Some(docs) = archive.collections.remove(&collection.name) {
+                count += (collection.import)((collection.db)(), docs)?;
+            }
+        }
+        import_files(&archive.files)?;
+        Ok(count)
+    }
+}
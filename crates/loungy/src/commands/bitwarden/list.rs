@@ -26,6 +26,7 @@ use serde_json::Value;
 use url::Url;
 
 use crate::{
+    archive::Archived,
     command,
     commands::{RootCommand, RootCommandBuilder},
     components::{
@@ -179,7 +180,7 @@ pub(super) enum BitwardenItem {
 
 #[derive(Serialize, Deserialize, Clone, Collection)]
 #[collection(name = "bitwarden-accounts")]
-pub(super) struct BitwardenAccount {
+pub(crate) struct BitwardenAccount {
     #[natural_id]
     pub id: String,
     pub client_id: String,
@@ -189,6 +190,18 @@ pub(super) struct BitwardenAccount {
     pub session: Option<String>,
 }
 
+// The account is still listed after importing, its client secret and password just have to be entered again
+impl Archived for BitwardenAccount {
+    fn without_secrets(self) -> Option<Self> {
+        Some(Self {
+            client_secret: String::new(),
+            password: None,
+            session: None,
+            ..self
+        })
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub(super) enum BitwardenVaultStatus {
@@ -210,7 +223,7 @@ impl BitwardenAccount {
     }
     pub async fn command(&self, args: Vec<&str>) -> anyhow::Result<Output> {
         let mut env: HashMap<String, String> = HashMap::new();
-        env.insert("PATH".to_string(), paths().path_env.clone());
+        env.insert("PATH".to_string(), paths().path_env().to_string());
         env.insert(
             "BITWARDENCLI_APPDATA_DIR".to_string(),
             self.path().to_string_lossy().to_string(),
@@ -308,7 +321,7 @@ impl BitwardenAccount {
 
 pub struct BitwardenCommandBuilder;
 
-pub(super) fn db() -> &'static Database {
+pub(crate) fn db() -> &'static Database {
     static DB: OnceLock<Database> = OnceLock::new();
     DB.get_or_init(LDb::init_collection::<BitwardenAccount>)
 }
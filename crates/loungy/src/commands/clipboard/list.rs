@@ -11,12 +11,12 @@
 
 use std::{
     cmp::Reverse,
-    collections::hash_map::DefaultHasher,
+    collections::{HashMap, HashSet, hash_map::DefaultHasher},
     hash::{Hash, Hasher},
     path::PathBuf,
     sync::{Arc, OnceLock},
     thread,
-    time::{Duration, Instant},
+    time::Duration,
 };
 
 use arboard::Clipboard;
@@ -25,9 +25,9 @@ use bonsaidb::{
     local::Database,
 };
 use gpui::{
-    AnyEntity, AnyView, App, AsyncApp, AvailableSpace, Bounds, Context, Entity, FontWeight,
-    ImageSource, IntoElement, ListAlignment, ListState, Render, WeakEntity, Window, canvas, div,
-    img, list, px,
+    AnyEntity, AnyView, App, AsyncApp, AvailableSpace, Bounds, Context, Entity, FontWeight, Global,
+    HighlightStyle, ImageSource, IntoElement, ListAlignment, ListState, Render, StyledText,
+    WeakEntity, Window, canvas, div, img, list, px,
 };
 use image::{DynamicImage, ImageBuffer};
 use jiff::{Span, Timestamp, ToSpan};
@@ -37,25 +37,34 @@ use serde::{Deserialize, Serialize};
 use url::Url;
 
 use crate::{
+    archive::Archived,
     command,
-    commands::{RootCommand, RootCommandBuilder},
+    commands::{RootCommand, RootCommandBuilder, snippets::list::Snippet},
     components::{
-        list::{AsyncListItems, Item, ItemBuilder, ListBuilder, ListItem},
+        list::{
+            Accessory, AsyncListItems, Item, ItemBuilder, ListBuilder, ListItem, item_id,
+            nucleo::fuzzy_match,
+        },
         shared::{Icon, Img, ImgMask, ImgSize, ObjectFit},
     },
     date::format_date,
     db::LDb,
     paths::paths,
-    platform::{
-        AppData, ClipboardWatcher, clipboard, close_and_paste, close_and_paste_file,
-        get_frontmost_application_data, ocr,
-    },
+    platform::{AppData, ClipboardWatcher, clipboard, close_and_paste, close_and_paste_file, ocr},
     state::{
         CommandTrait, LAction, Shortcut, StateItem, StateModel, StateViewBuilder, StateViewContext,
     },
     theme::LTheme,
 };
 
+use super::{
+    push,
+    retention::{PRUNE_INTERVAL, RetainedEntry, RetentionPolicy},
+    search::{ClipboardTerms, search, set_query, snippet},
+    sensitive::{SensitiveRules, Sensitivity},
+    transform::TransformListBuilder,
+};
+
 #[derive(Clone)]
 pub struct ClipboardListBuilder {
     view: Entity<AsyncListItems>,
@@ -107,7 +116,43 @@ impl StateViewBuilder for ClipboardListBuilder {
 
         AsyncListItems::loader(&self.view, &context.actions, cx);
         let view = self.view.clone();
+        let images = self.view.clone();
         ListBuilder::new()
+            .filter(move |this, cx| {
+                let query = this.query.get_text(cx);
+                set_query(&query);
+                if query.trim().is_empty() {
+                    return this.items_all.clone();
+                }
+                // Entries are already sorted by recency, which is kept for full-text matches
+                match search(&query) {
+                    Ok(Some(ids)) => {
+                        let mut ids: HashSet<u64> = ids.into_iter().map(item_id).collect();
+                        // Images have no indexed text, so they are matched by their title instead
+                        let images = images
+                            .read(cx)
+                            .items
+                            .get("Image")
+                            .cloned()
+                            .unwrap_or_default();
+                        ids.extend(
+                            fuzzy_match(&query, images, false)
+                                .iter()
+                                .map(|item| item.get_id()),
+                        );
+                        this.items_all
+                            .iter()
+                            .filter(|item| ids.contains(&item.get_id()))
+                            .cloned()
+                            .collect()
+                    }
+                    Ok(None) => fuzzy_match(&query, this.items_all.clone(), false),
+                    Err(err) => {
+                        error!("Failed to search clipboard: {:?}", err);
+                        fuzzy_match(&query, this.items_all.clone(), false)
+                    }
+                }
+            })
             .build(
                 move |list, _, cx| {
                     let t = list.actions.get_dropdown_value(cx);
@@ -129,7 +174,7 @@ impl StateViewBuilder for ClipboardListBuilder {
 }
 
 #[derive(Clone, Serialize, Deserialize)]
-enum ClipboardKind {
+pub(super) enum ClipboardKind {
     Text {
         characters: u64,
         words: u64,
@@ -149,15 +194,17 @@ enum ClipboardKind {
 }
 
 #[derive(Clone, Serialize, Deserialize, Collection)]
-#[collection(name = "clipboard.detail")]
-struct ClipboardDetail {
+#[collection(name = "clipboard.detail", views = [ClipboardTerms])]
+pub(crate) struct ClipboardDetail {
     #[natural_id]
     id: u64,
     application: String,
     application_icon: Option<PathBuf>,
-    kind: ClipboardKind,
+    pub(super) kind: ClipboardKind,
 }
 
+impl Archived for ClipboardDetail {}
+
 #[derive(Clone, Serialize, Deserialize)]
 enum ClipboardListItemKind {
     Text,
@@ -187,7 +234,7 @@ impl From<ClipboardListItemKind> for String {
 
 #[derive(Clone, Serialize, Deserialize, Collection)]
 #[collection(name = "clipboard.item")]
-struct ClipboardListItem {
+pub(crate) struct ClipboardListItem {
     #[natural_id]
     id: u64,
     title: String,
@@ -195,10 +242,24 @@ struct ClipboardListItem {
     copied_last: Timestamp,
     kind: ClipboardListItemKind,
     copy_count: u32,
+    // Set for sensitive entries, which are deleted again once it passes
+    #[serde(default)]
+    expires: Option<Timestamp>,
+    // Pinned entries are never pruned
+    #[serde(default)]
+    pinned: bool,
 }
 
+impl Archived for ClipboardListItem {}
+
 impl ClipboardListItem {
-    fn new(id: u64, title: impl ToString, kind: ClipboardKind, app: &Option<AppData>) -> Self {
+    fn new(
+        id: u64,
+        title: impl ToString,
+        kind: ClipboardKind,
+        app: &Option<AppData>,
+        expires: Option<Timestamp>,
+    ) -> Self {
         let (application, application_icon) = app
             .as_ref()
             .map(|data| (data.name.clone(), Some(data.icon_path.clone())))
@@ -211,6 +272,8 @@ impl ClipboardListItem {
             copied_first: Timestamp::now(),
             copy_count: 1,
             kind: kind.clone().into(),
+            expires,
+            pinned: false,
         };
         let _ = item.clone().push_into(db_items());
         let detail = ClipboardDetail {
@@ -242,7 +305,14 @@ impl ClipboardListItem {
                 },
                 self.title.clone(),
                 None,
-                vec![],
+                if self.pinned {
+                    vec![Accessory::new(
+                        "Pinned",
+                        Some(Img::default().icon(Icon::Pin)),
+                    )]
+                } else {
+                    vec![]
+                },
             ),
         )
         .keywords(vec![self.title.clone()])
@@ -275,6 +345,24 @@ impl ClipboardListItem {
                     },
                     false,
                 ),
+                LAction::new(
+                    Img::default().icon(if self.pinned { Icon::PinOff } else { Icon::Pin }),
+                    if self.pinned { "Unpin" } else { "Pin" },
+                    Some(Shortcut::new("p").cmd().shift()),
+                    {
+                        let self_clone = self.clone();
+                        let view = cx.view().clone();
+                        move |actions, cx| match self_clone.toggle_pin(view.downgrade(), cx) {
+                            Ok(true) => actions.toast.success("Pinned clipboard entry", cx),
+                            Ok(false) => actions.toast.success("Unpinned clipboard entry", cx),
+                            Err(err) => {
+                                error!("Failed to pin clipboard entry: {:?}", err);
+                                actions.toast.error("Failed to pin clipboard entry", cx);
+                            }
+                        }
+                    },
+                    false,
+                ),
                 LAction::new(
                     Img::default().icon(Icon::Trash),
                     "Delete",
@@ -296,6 +384,35 @@ impl ClipboardListItem {
                     false,
                 ),
             ];
+            // Inserted before the kind specific actions, so "Open" still comes first for links
+            if !matches!(self.kind, ClipboardListItemKind::Image { .. }) {
+                actions.insert(
+                    1,
+                    LAction::new(
+                        Img::default().icon(Icon::Wand),
+                        "Transform",
+                        Some(Shortcut::new("t").cmd()),
+                        {
+                            let id = self.id;
+                            move |actions, cx| {
+                                let Ok(Some(detail)) = ClipboardDetail::get(&id, db_detail())
+                                else {
+                                    actions.toast.error("Clipboard entry not found", cx);
+                                    return;
+                                };
+                                match detail.contents.kind {
+                                    ClipboardKind::Text { text, .. }
+                                    | ClipboardKind::Url { url: text, .. } => {
+                                        push(TransformListBuilder { text }, cx);
+                                    }
+                                    ClipboardKind::Image { .. } => {}
+                                }
+                            }
+                        },
+                        false,
+                    ),
+                );
+            }
             match self.kind.clone() {
                 ClipboardListItemKind::Image { thumbnail } => actions.insert(
                     1,
@@ -335,7 +452,33 @@ impl ClipboardListItem {
                         false,
                     ),
                 ),
-                _ => {}
+                ClipboardListItemKind::Text => {}
+            }
+            if !matches!(self.kind, ClipboardListItemKind::Image { .. }) {
+                actions.push(LAction::new(
+                    Img::default().icon(Icon::TextQuote),
+                    "Save as Snippet",
+                    Some(Shortcut::new("s").cmd()),
+                    {
+                        let id = self.id;
+                        let title = self.title.clone();
+                        move |actions, cx| {
+                            let text = match ClipboardDetail::get(&id, db_detail()) {
+                                Ok(Some(detail)) => match detail.contents.kind {
+                                    ClipboardKind::Text { text, .. }
+                                    | ClipboardKind::Url { url: text, .. } => text,
+                                    ClipboardKind::Image { .. } => return,
+                                },
+                                _ => {
+                                    actions.toast.error("Clipboard entry not found", cx);
+                                    return;
+                                }
+                            };
+                            Snippet::new(id, &title, text).edit(cx);
+                        }
+                    },
+                    false,
+                ));
             }
             actions
         })
@@ -361,10 +504,98 @@ impl ClipboardListItem {
         }
         Ok(())
     }
+    // Returns whether the entry is pinned now
+    fn toggle_pin(&self, view: WeakEntity<AsyncListItems>, cx: &mut App) -> anyhow::Result<bool> {
+        let Some(mut item) = Self::get(&self.id, db_items())? else {
+            return Err(anyhow::anyhow!("Clipboard entry {} not found", self.id));
+        };
+        item.contents.pinned = !item.contents.pinned;
+        // Pinning a sensitive entry keeps it as well
+        item.contents.expires = None;
+        item.update(db_items())?;
+        let entry = item.contents;
+        let _ = view.update(cx, |view, cx| {
+            let item = entry.get_item(cx);
+            view.push(entry.kind.clone().into(), item, cx);
+        });
+        Ok(entry.pinned)
+    }
+    fn image_path(&self) -> Option<PathBuf> {
+        let ClipboardListItemKind::Image { thumbnail } = &self.kind else {
+            return None;
+        };
+        Some(thumbnail.with_file_name(format!("{}.png", self.id)))
+    }
+    // Size of the image and its thumbnail on disk
+    fn image_bytes(&self) -> u64 {
+        let ClipboardListItemKind::Image { thumbnail } = &self.kind else {
+            return 0;
+        };
+        [Some(thumbnail.clone()), self.image_path()]
+            .into_iter()
+            .flatten()
+            .filter_map(|path| path.metadata().ok())
+            .map(|meta| meta.len())
+            .sum()
+    }
+    fn enforce_retention(
+        policy: &RetentionPolicy,
+        view: WeakEntity<AsyncListItems>,
+        cx: &mut App,
+    ) -> anyhow::Result<()> {
+        let items: HashMap<u64, Self> = Self::all(db_items())
+            .query()?
+            .into_iter()
+            .map(|item| (item.contents.id, item.contents))
+            .collect();
+        let entries = items
+            .values()
+            .filter(|item| !item.pinned)
+            .map(|item| RetainedEntry {
+                id: item.id,
+                kind: item.kind.clone().into(),
+                copied_last: item.copied_last,
+                expires: item.expires,
+                bytes: item.image_bytes(),
+            })
+            .collect();
+        for id in policy.expired(entries, Timestamp::now()) {
+            if let Some(item) = items.get(&id) {
+                item.delete(view.clone(), cx)?;
+            }
+        }
+        Ok(())
+    }
+    // Deletes the entry once it expires, retention catches anything left over from before a restart
+    fn expire(&self, view: WeakEntity<AsyncListItems>, cx: &mut AsyncApp) {
+        let Some(expires) = self.expires else {
+            return;
+        };
+        let delay =
+            Duration::try_from(Timestamp::now().duration_until(expires)).unwrap_or_default();
+        let item = self.clone();
+        cx.spawn(async move |cx| {
+            cx.background_executor().timer(delay).await;
+            // Copied again in the meantime, which moved the expiry
+            if let Ok(Some(current)) = Self::get(&item.id, db_items()) {
+                if current.contents.expires != Some(expires) {
+                    return;
+                }
+            }
+            let _ = cx.update(|cx| {
+                if let Err(err) = item.delete(view, cx) {
+                    error!("Failed to delete sensitive clipboard entry: {:?}", err);
+                }
+            });
+        })
+        .detach();
+    }
     fn prune(age: Span, view: WeakEntity<AsyncListItems>, cx: &mut App) -> anyhow::Result<()> {
         let items = Self::all(db_items()).query()?;
         for item in items {
-            if item.contents.copied_last < Timestamp::now().checked_sub(age).unwrap() {
+            if !item.contents.pinned
+                && item.contents.copied_last < Timestamp::now().checked_sub(age).unwrap()
+            {
                 let _ = item.contents.delete(view.clone(), cx);
             }
         }
@@ -405,7 +636,24 @@ impl ClipboardPreview {
                 .clone()
             {
                 ClipboardKind::Text { text, .. } | ClipboardKind::Url { url: text, .. } => {
-                    div().p_2().w_full().child(text.clone()).into_any_element()
+                    match snippet(&text) {
+                        Some((snippet, ranges)) => {
+                            let theme = cx.global::<LTheme>();
+                            let highlight = HighlightStyle {
+                                color: Some(theme.base),
+                                background_color: Some(theme.yellow),
+                                ..Default::default()
+                            };
+                            div()
+                                .p_2()
+                                .w_full()
+                                .child(StyledText::new(snippet).with_highlights(
+                                    ranges.into_iter().map(|range| (range, highlight)),
+                                ))
+                                .into_any_element()
+                        }
+                        None => div().p_2().w_full().child(text.clone()).into_any_element(),
+                    }
                 }
                 ClipboardKind::Image {
                     width,
@@ -589,31 +837,96 @@ impl StateViewBuilder for ClipboardPreview {
     }
 }
 
-pub(super) fn db_items() -> &'static Database {
+pub(crate) fn db_items() -> &'static Database {
     static DB: OnceLock<Database> = OnceLock::new();
     DB.get_or_init(LDb::init_collection::<ClipboardListItem>)
 }
 
-pub(super) fn db_detail() -> &'static Database {
+pub(crate) fn db_detail() -> &'static Database {
     static DB: OnceLock<Database> = OnceLock::new();
     DB.get_or_init(LDb::init_collection::<ClipboardDetail>)
 }
 
+// The entries the clipboard command keeps in memory, so they can be reloaded after an import
+pub(crate) struct ClipboardHistory(WeakEntity<AsyncListItems>);
+
+impl Global for ClipboardHistory {}
+
+impl ClipboardHistory {
+    fn load(list_items: &mut AsyncListItems, cx: &mut Context<AsyncListItems>) {
+        // Kinds without any entries left are emptied rather than kept
+        let mut items: HashMap<String, Vec<Item>> = list_items
+            .items
+            .keys()
+            .map(|key| (key.clone(), vec![]))
+            .collect();
+        for item in ClipboardListItem::all(db_items())
+            .query()
+            .unwrap_or_default()
+        {
+            let item = item.contents;
+            items
+                .entry(item.kind.clone().into())
+                .or_default()
+                .push(item.get_item(cx));
+        }
+        for (key, items) in items {
+            list_items.update(key, items, cx);
+        }
+    }
+    pub(crate) fn reload(cx: &mut App) {
+        let Some(view) = cx.try_global::<Self>().and_then(|this| this.0.upgrade()) else {
+            return;
+        };
+        view.update(cx, |this, cx| Self::load(this, cx));
+    }
+}
+
+// The application a new entry was copied from and its expiry, `None` if it shouldn't be recorded at all.
+// Runs in the background, as looking up the frontmost application and clipboard types can spawn processes.
+async fn inspect(
+    text: Option<String>,
+    cx: &AsyncApp,
+) -> Option<(Option<AppData>, Option<Timestamp>)> {
+    cx.background_executor()
+        .spawn(async move {
+            let rules = SensitiveRules::load();
+            let source = rules.source();
+            let expires = match rules.check(&source, text.as_deref()) {
+                Sensitivity::None => None,
+                Sensitivity::Skip => return None,
+                Sensitivity::Expire(expires) => Some(expires),
+            };
+            Some((source.app, expires))
+        })
+        .await
+}
+
 pub struct ClipboardCommandBuilder;
 command!(ClipboardCommandBuilder);
 impl RootCommandBuilder for ClipboardCommandBuilder {
     fn build(&self, window: &mut Window, cx: &mut App) -> RootCommand {
         let view = cx.new(|cx| {
             let mut list_items = AsyncListItems::new();
-            let items = ClipboardListItem::all(db_items())
-                .query()
-                .unwrap_or_default();
-            for item in items {
-                let item = item.clone().contents;
-                list_items.push(item.kind.clone().into(), item.get_item(cx), cx);
-            }
+            ClipboardHistory::load(&mut list_items, cx);
             ClipboardWatcher::init(cx);
 
+            // Applies the retention rules right away and then periodically, picking up changed settings
+            cx.spawn(async move |view, cx| {
+                loop {
+                    let policy = RetentionPolicy::load();
+                    let _ = cx.update(|cx| {
+                        if let Err(err) =
+                            ClipboardListItem::enforce_retention(&policy, view.clone(), cx)
+                        {
+                            error!("Failed to prune clipboard: {:?}", err);
+                        }
+                    });
+                    cx.background_executor().timer(PRUNE_INTERVAL).await;
+                }
+            })
+            .detach();
+
             cx.spawn(|view, cx| async move {
                 let mut cp = Clipboard::new().unwrap();
                 let mut hash: u64 = 0;
@@ -621,105 +934,90 @@ impl RootCommandBuilder for ClipboardCommandBuilder {
                 if !cache.exists() {
                     let _ = std::fs::create_dir_all(&cache);
                 }
-                let mut now = Instant::now();
                 clipboard(
                     |cx| {
-                        if Instant::now() - now > Duration::from_secs(3600) {
-                            now = Instant::now();
-                            // Prune clipboard history every hour, keeping entries for a week
-                            let _ = cx.update_window(cx.window_handle(), |_, cx| {
-                                let _ = ClipboardListItem::prune(
-                                    ToSpan::seconds(60 * 60 * 24 * 7),
-                                    view.clone(),
-                                    cx,
-                                );
-                            });
-                        }
-
-                        let app = get_frontmost_application_data();
-                        let condition = |app: &Option<AppData>, cx: &mut AsyncApp| {
-                            if !ClipboardWatcher::is_enabled(cx) {
-                                ClipboardWatcher::enabled(cx);
-                                return false;
-                            }
-
-                            // TODO: make this configurable and platform independent
-                            if let Some(app) = app {
-                                if matches!(
-                                    app.id.as_str(),
-                                    "com.apple.systempreferences" | "com.apple.keychainaccess"
-                                ) {
-                                    return false;
-                                }
-                            }
-
-                            true
-                        };
                         if let Ok(text) = cp.get_text() {
                             let mut hasher = DefaultHasher::new();
                             text.hash(&mut hasher);
                             let new_hash = hasher.finish();
                             if new_hash != hash {
                                 hash = new_hash;
-                                if !condition(&app, cx) {
+                                if !ClipboardWatcher::is_enabled(cx) {
+                                    ClipboardWatcher::enabled(cx);
                                     return;
                                 }
-                                let entry = if let Ok(Some(mut item)) =
-                                    ClipboardListItem::get(&hash, db_items())
-                                {
-                                    item.contents.copied_last = jiff::Timestamp::now();
-                                    item.contents.copy_count += 1;
-                                    let _ = item.update(db_items());
-                                    item.contents.clone()
-                                } else {
-                                    let url = Url::parse(&text);
-                                    if url.is_ok() && {
-                                        let url = url.unwrap();
-                                        !url.cannot_be_a_base() && url.scheme().starts_with("http")
-                                    } {
-                                        ClipboardListItem::new(
-                                            hash,
-                                            {
-                                                let mut text = text.trim().replace('\n', " ");
-                                                if text.len() > 25 {
-                                                    text.truncate(25);
-                                                    text.push_str("...");
-                                                }
-                                                text
-                                            },
-                                            ClipboardKind::Url {
-                                                characters: text.chars().count() as u64,
-                                                url: text,
-                                                title: "".to_string(),
-                                            },
-                                            &app,
-                                        )
+                                let view = view.clone();
+                                cx.spawn(async move |cx| {
+                                    let Some((app, expires)) =
+                                        inspect(Some(text.clone()), cx).await
+                                    else {
+                                        return;
+                                    };
+                                    let entry = if let Ok(Some(mut item)) =
+                                        ClipboardListItem::get(&hash, db_items())
+                                    {
+                                        item.contents.copied_last = jiff::Timestamp::now();
+                                        item.contents.copy_count += 1;
+                                        if !item.contents.pinned {
+                                            item.contents.expires = expires;
+                                        }
+                                        let _ = item.update(db_items());
+                                        item.contents.clone()
                                     } else {
-                                        ClipboardListItem::new(
-                                            hash,
-                                            {
-                                                let mut text = text.trim().replace('\n', " ");
-                                                if text.len() > 25 {
-                                                    text.truncate(25);
-                                                    text.push_str("...");
-                                                }
-                                                text
-                                            },
-                                            ClipboardKind::Text {
-                                                characters: text.chars().count() as u64,
-                                                words: text.split_whitespace().count() as u64,
-                                                text: text.clone(),
-                                            },
-                                            &app,
-                                        )
-                                    }
-                                };
-                                let _ = cx.update(|cx| {
-                                    let _ = view.update(cx, |view: &mut AsyncListItems, cx| {
-                                        let item = entry.get_item(cx);
-                                        view.push(entry.kind.into(), item, cx);
+                                        let url = Url::parse(&text);
+                                        if url.is_ok() && {
+                                            let url = url.unwrap();
+                                            !url.cannot_be_a_base()
+                                                && url.scheme().starts_with("http")
+                                        } {
+                                            ClipboardListItem::new(
+                                                hash,
+                                                {
+                                                    let mut text = text.trim().replace('\n', " ");
+                                                    if text.len() > 25 {
+                                                        text.truncate(25);
+                                                        text.push_str("...");
+                                                    }
+                                                    text
+                                                },
+                                                ClipboardKind::Url {
+                                                    characters: text.chars().count() as u64,
+                                                    url: text,
+                                                    title: "".to_string(),
+                                                },
+                                                &app,
+                                                expires,
+                                            )
+                                        } else {
+                                            ClipboardListItem::new(
+                                                hash,
+                                                {
+                                                    let mut text = text.trim().replace('\n', " ");
+                                                    if text.len() > 25 {
+                                                        text.truncate(25);
+                                                        text.push_str("...");
+                                                    }
+                                                    text
+                                                },
+                                                ClipboardKind::Text {
+                                                    characters: text.chars().count() as u64,
+                                                    words: text.split_whitespace().count() as u64,
+                                                    text: text.clone(),
+                                                },
+                                                &app,
+                                                expires,
+                                            )
+                                        }
+                                    };
+                                    entry.expire(view.clone(), cx);
+                                    let _ = cx.update(|cx| {
+                                        let _ = view.update(cx, |view: &mut AsyncListItems, cx| {
+                                            let item = entry.get_item(cx);
+                                            view.push(entry.kind.into(), item, cx);
+                                        });
                                     });
-                                });
+                                })
+                                .detach();
                             }
                         } else if let Ok(image) = cp.get_image() {
                             let mut hasher = DefaultHasher::new();
@@ -727,57 +1025,71 @@ impl RootCommandBuilder for ClipboardCommandBuilder {
                             let new_hash = hasher.finish();
                             if new_hash != hash {
                                 hash = new_hash;
-                                if !condition(&app, cx) {
+                                if !ClipboardWatcher::is_enabled(cx) {
+                                    ClipboardWatcher::enabled(cx);
                                     return;
                                 }
-                                let entry = if let Ok(Some(mut item)) =
-                                    ClipboardListItem::get(&hash, db_items())
-                                {
-                                    item.contents.copied_last = Timestamp::now();
-                                    item.contents.copy_count += 1;
-                                    let _ = item.update(db_items());
-                                    item.contents.clone()
-                                } else {
-                                    let width = image.width.try_into().unwrap();
-                                    let height = image.height.try_into().unwrap();
-                                    let path = cache.join(format!("{}.png", hash));
-                                    let thumbnail = cache.join(format!("{}.thumb.png", hash));
-                                    // Spawn a thread to generate thumbnail and saving to filesystem.
+                                let view = view.clone();
+                                let cache = cache.clone();
+                                cx.spawn(async move |cx| {
+                                    let Some((app, expires)) = inspect(None, cx).await else {
+                                        return;
+                                    };
+                                    let entry = if let Ok(Some(mut item)) =
+                                        ClipboardListItem::get(&hash, db_items())
                                     {
-                                        let path = path.clone();
-                                        let thumbnail = thumbnail.clone();
-                                        thread::spawn(move || {
-                                            let image = DynamicImage::ImageRgba8(
-                                                ImageBuffer::from_vec(
-                                                    width,
-                                                    height,
-                                                    image.bytes.to_vec(),
-                                                )
-                                                .unwrap(),
-                                            );
-                                            let _ = image.save(&path);
-                                            let t = image.thumbnail(64, 64);
-                                            let _ = t.save(&thumbnail);
+                                        item.contents.copied_last = Timestamp::now();
+                                        item.contents.copy_count += 1;
+                                        if !item.contents.pinned {
+                                            item.contents.expires = expires;
+                                        }
+                                        let _ = item.update(db_items());
+                                        item.contents.clone()
+                                    } else {
+                                        let width = image.width.try_into().unwrap();
+                                        let height = image.height.try_into().unwrap();
+                                        let path = cache.join(format!("{}.png", hash));
+                                        let thumbnail = cache.join(format!("{}.thumb.png", hash));
+                                        // Spawn a thread to generate thumbnail and saving to filesystem.
+                                        {
+                                            let path = path.clone();
+                                            let thumbnail = thumbnail.clone();
+                                            thread::spawn(move || {
+                                                let image = DynamicImage::ImageRgba8(
+                                                    ImageBuffer::from_vec(
+                                                        width,
+                                                        height,
+                                                        image.bytes.to_vec(),
+                                                    )
+                                                    .unwrap(),
+                                                );
+                                                let _ = image.save(&path);
+                                                let t = image.thumbnail(64, 64);
+                                                let _ = t.save(&thumbnail);
+                                            });
+                                        }
+                                        ClipboardListItem::new(
+                                            hash,
+                                            format!("Image ({}x{})", width, height),
+                                            ClipboardKind::Image {
+                                                width,
+                                                height,
+                                                path,
+                                                thumbnail,
+                                            },
+                                            &app,
+                                            expires,
+                                        )
+                                    };
+                                    entry.expire(view.clone(), cx);
+                                    let _ = cx.update_window(cx.window_handle(), |_, cx| {
+                                        let _ = view.update(cx, |view: &mut AsyncListItems, cx| {
+                                            let item = entry.get_item(cx);
+                                            view.push(entry.kind.into(), item, cx);
                                         });
-                                    }
-                                    ClipboardListItem::new(
-                                        hash,
-                                        format!("Image ({}x{})", width, height),
-                                        ClipboardKind::Image {
-                                            width,
-                                            height,
-                                            path,
-                                            thumbnail,
-                                        },
-                                        &app,
-                                    )
-                                };
-                                let _ = cx.update_window(cx.window_handle(), |_, cx| {
-                                    let _ = view.update(cx, |view: &mut AsyncListItems, cx| {
-                                        let item = entry.get_item(cx);
-                                        view.push(entry.kind.into(), item, cx);
                                     });
-                                });
+                                })
+                                .detach();
                             }
                         }
                     },
@@ -788,6 +1100,7 @@ impl RootCommandBuilder for ClipboardCommandBuilder {
             .detach();
             list_items
         });
+        cx.set_global(ClipboardHistory(view.downgrade()));
 
         RootCommand::new(
             "clipboard",
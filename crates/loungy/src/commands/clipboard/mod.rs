@@ -9,4 +9,21 @@
  *
  */
 
+use gpui::{App, AppContext};
+
+use crate::state::{StateModel, StateViewBuilder};
+
 pub mod list;
+mod retention;
+mod search;
+mod sensitive;
+mod transform;
+
+// Pushes a view from within an action, which doesn't get a handle to the window
+pub(super) fn push(builder: impl StateViewBuilder, cx: &mut App) {
+    if let Some(handle) = cx.windows().first().copied() {
+        let _ = cx.update_window(handle, |_, window, cx| {
+            StateModel::update(|this, cx| this.push(builder, window, cx), cx);
+        });
+    }
+}
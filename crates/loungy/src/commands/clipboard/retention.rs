@@ -0,0 +1,118 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+// Rules deciding which clipboard entries get pruned, see `ClipboardListItem::enforce_retention`
+
+use std::{cmp::Reverse, collections::HashMap, time::Duration};
+
+use jiff::{Timestamp, ToSpan};
+
+use crate::{commands::root::preferences::Preferences, config::Config, state::CommandTrait};
+
+use super::list::ClipboardCommandBuilder;
+
+// How often the retention rules are applied
+pub const PRUNE_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+// What the rules need to know about an entry
+pub struct RetainedEntry {
+    pub id: u64,
+    // "Text", "Link" or "Image"
+    pub kind: String,
+    pub copied_last: Timestamp,
+    // Sensitive entries are only kept until then
+    pub expires: Option<Timestamp>,
+    // Size of the image and its thumbnail on disk, 0 for anything else
+    pub bytes: u64,
+}
+
+// A limit of 0 means no limit
+#[derive(Clone, Debug)]
+pub struct RetentionPolicy {
+    pub days: u64,
+    // Kind to days, overriding `days`
+    pub kind_days: HashMap<String, u64>,
+    pub max_entries: u64,
+    pub max_image_bytes: u64,
+}
+
+impl RetentionPolicy {
+    // The preferences, with anything set in the config taking precedence:
+    //
+    // [options."loungy::commands::clipboard::list"]
+    // retention_days = 30
+    // max_entries = 5000
+    // max_image_mb = 500
+    // kind_retention_days = { link = 90, image = 7 }
+    pub fn load() -> Self {
+        let id = ClipboardCommandBuilder.command();
+        let preferences = Preferences::get();
+        let kind_days = Config::option::<HashMap<String, u64>>(&id, "kind_retention_days")
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(kind, days)| (kind.to_lowercase(), days))
+            .collect();
+        Self {
+            days: Config::option(&id, "retention_days")
+                .unwrap_or(preferences.clipboard_retention_days),
+            kind_days,
+            max_entries: Config::option(&id, "max_entries")
+                .unwrap_or(preferences.clipboard_max_entries),
+            max_image_bytes: Config::option::<u64>(&id, "max_image_mb")
+                .unwrap_or(preferences.clipboard_max_image_mb)
+                * 1024
+                * 1024,
+        }
+    }
+    pub fn days(&self, kind: &str) -> u64 {
+        self.kind_days
+            .get(&kind.to_lowercase())
+            .copied()
+            .unwrap_or(self.days)
+    }
+    // Ids of the entries breaking any of the rules. Expiry and age are checked first, then
+    // the count and the image size, both of which drop the least recently copied entries.
+    pub fn expired(&self, mut entries: Vec<RetainedEntry>, now: Timestamp) -> Vec<u64> {
+        let mut expired = vec![];
+        entries.retain(|entry| {
+            let days = self.days(&entry.kind);
+            let keep = entry.expires.is_none_or(|expires| expires > now)
+                && (days == 0
+                    || now
+                        .checked_sub((days as i64 * 24).hours())
+                        .is_ok_and(|cutoff| entry.copied_last >= cutoff));
+            if !keep {
+                expired.push(entry.id);
+            }
+            keep
+        });
+
+        entries.sort_by_key(|entry| Reverse(entry.copied_last));
+        if self.max_entries > 0 && entries.len() as u64 > self.max_entries {
+            expired.extend(
+                entries
+                    .drain(self.max_entries as usize..)
+                    .map(|entry| entry.id),
+            );
+        }
+
+        if self.max_image_bytes > 0 {
+            let mut total = 0;
+            for entry in entries.iter().filter(|entry| entry.bytes > 0) {
+                total += entry.bytes;
+                if total > self.max_image_bytes {
+                    expired.push(entry.id);
+                }
+            }
+        }
+        expired
+    }
+}
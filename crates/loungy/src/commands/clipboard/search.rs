@@ -0,0 +1,150 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+// Full-text search over the clipboard contents, backed by an inverted index kept in a bonsaidb view
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    ops::Range,
+};
+
+use bonsaidb::core::{
+    document::{CollectionDocument, Emit},
+    schema::{CollectionMapReduce, SerializedView, View, ViewMapResult, ViewSchema},
+};
+use parking_lot::RwLock;
+use regex::{Regex, RegexBuilder};
+
+use super::list::{ClipboardDetail, ClipboardKind, db_detail};
+
+// Terms outside of this length are too common or too unlikely to be searched for
+const TERM_LENGTH: Range<usize> = 2..33;
+// Characters of context shown above the first match
+const SNIPPET_CONTEXT: usize = 160;
+
+// The current query, so previews can highlight what was searched for
+static QUERY: RwLock<String> = RwLock::new(String::new());
+
+// Lowercased words of the text, with how often each one appears
+fn terms(text: &str) -> BTreeMap<String, u32> {
+    let mut terms = BTreeMap::new();
+    for term in text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|term| TERM_LENGTH.contains(&term.chars().count()))
+    {
+        *terms.entry(term.to_lowercase()).or_default() += 1;
+    }
+    terms
+}
+
+fn searchable(kind: &ClipboardKind) -> Option<String> {
+    match kind {
+        ClipboardKind::Text { text, .. } => Some(text.clone()),
+        ClipboardKind::Url { url, title, .. } => Some(format!("{} {}", url, title)),
+        ClipboardKind::Image { .. } => None,
+    }
+}
+
+// Maps every term of an entry to the entry, the value being the number of occurrences
+#[derive(Debug, Clone, View, ViewSchema)]
+#[view(collection = ClipboardDetail, key = String, value = u32, name = "terms")]
+pub(crate) struct ClipboardTerms;
+
+impl CollectionMapReduce for ClipboardTerms {
+    fn map<'doc>(
+        &self,
+        document: CollectionDocument<ClipboardDetail>,
+    ) -> ViewMapResult<'doc, Self> {
+        let Some(text) = searchable(&document.contents.kind) else {
+            return Ok(Default::default());
+        };
+        terms(&text)
+            .into_iter()
+            .map(|(term, count)| document.header.emit_key_and_value(term, count))
+            .collect()
+    }
+}
+
+pub fn set_query(query: &str) {
+    *QUERY.write() = query.to_string();
+}
+
+// Ids of the entries containing every word of the query, the last word may be incomplete.
+// `None` if the query has no searchable words at all.
+pub fn search(query: &str) -> anyhow::Result<Option<HashSet<u64>>> {
+    let words: Vec<String> = terms(query).into_keys().collect();
+    if words.is_empty() {
+        return Ok(None);
+    }
+    let last = query
+        .split(|c: char| !c.is_alphanumeric())
+        .next_back()
+        .unwrap_or_default()
+        .to_lowercase();
+    let mut result: Option<HashSet<u64>> = None;
+    for word in words {
+        let entries = ClipboardTerms::entries(db_detail());
+        let mappings = if word == last {
+            entries.with_key_prefix(&word).query()?
+        } else {
+            entries.with_key(&word).query()?
+        };
+        let ids: HashSet<u64> = mappings
+            .into_iter()
+            .filter_map(|mapping| mapping.source.id.deserialize::<u64>().ok())
+            .collect();
+        let ids = match result {
+            Some(result) => result.intersection(&ids).copied().collect(),
+            None => ids,
+        };
+        if ids.is_empty() {
+            return Ok(Some(ids));
+        }
+        result = Some(ids);
+    }
+    Ok(result)
+}
+
+fn query_regex() -> Option<Regex> {
+    let words: Vec<String> = terms(&QUERY.read())
+        .into_keys()
+        .map(|word| regex::escape(&word))
+        .collect();
+    if words.is_empty() {
+        return None;
+    }
+    RegexBuilder::new(&words.join("|"))
+        .case_insensitive(true)
+        .build()
+        .ok()
+}
+
+// The text from shortly before the first match of the current query, with the ranges of all matches in it
+pub fn snippet(text: &str) -> Option<(String, Vec<Range<usize>>)> {
+    let regex = query_regex()?;
+    let first = regex.find(text)?.start();
+    let mut start = first.saturating_sub(SNIPPET_CONTEXT);
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    // Start at a line break if there is one close enough
+    if let Some(line) = text[start..first].find('\n') {
+        start += line + 1;
+    }
+    let prefix = if start > 0 { "…" } else { "" };
+    let snippet = format!("{}{}", prefix, &text[start..]);
+    let ranges = regex
+        .find_iter(&snippet)
+        .map(|m| m.range())
+        .filter(|range| range.start >= prefix.len())
+        .collect();
+    Some((snippet, ranges))
+}
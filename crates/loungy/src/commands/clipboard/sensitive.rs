@@ -0,0 +1,141 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+// Rules keeping passwords, tokens and other secrets out of the clipboard history
+
+use jiff::{Timestamp, ToSpan};
+use log::error;
+use regex::Regex;
+
+use crate::{
+    config::Config,
+    platform::{AppData, clipboard_types, get_frontmost_application_data},
+    state::CommandTrait,
+};
+
+use super::list::ClipboardCommandBuilder;
+
+// Matched against the id or the name of the frontmost application, on Linux its `WM_CLASS`
+const DEFAULT_APPS: [&str; 11] = [
+    "com.apple.systempreferences",
+    "com.apple.keychainaccess",
+    "com.1password.1password",
+    "com.agilebits.onepassword7",
+    "com.bitwarden.desktop",
+    "org.keepassxc.keepassxc",
+    "1password",
+    "bitwarden",
+    "keepassxc",
+    "seahorse",
+    "kwalletmanager5",
+];
+
+// Markers password managers put next to what they copy, see http://nspasteboard.org
+const CONCEALED_TYPES: [&str; 6] = [
+    "org.nspasteboard.ConcealedType",
+    "org.nspasteboard.TransientType",
+    "org.nspasteboard.AutoGeneratedType",
+    "x-kde-passwordManagerHint",
+    "ExcludeClipboardContentFromMonitorProcessing",
+    "Clipboard Viewer Ignore",
+];
+
+pub enum Sensitivity {
+    None,
+    Skip,
+    // Kept, but deleted again at the given time
+    Expire(Timestamp),
+}
+
+// Where a copy came from, gathered together so it can be read off the main thread
+pub struct CopySource {
+    pub app: Option<AppData>,
+    types: Vec<String>,
+}
+
+pub struct SensitiveRules {
+    apps: Vec<String>,
+    patterns: Vec<Regex>,
+    concealed: bool,
+    expire_seconds: u64,
+}
+
+impl SensitiveRules {
+    // Read from the config on every change, so edits apply right away:
+    //
+    // [options."loungy::commands::clipboard::list"]
+    // ignore_apps = ["com.1password.1password"]
+    // ignore_patterns = ["^ghp_[A-Za-z0-9]{36}$"]
+    // ignore_concealed = true
+    // sensitive_expire_seconds = 0
+    pub fn load() -> Self {
+        let id = ClipboardCommandBuilder.command();
+        let patterns = Config::option::<Vec<String>>(&id, "ignore_patterns")
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|pattern| {
+                Regex::new(&pattern)
+                    .inspect_err(|err| error!("Invalid clipboard pattern {}: {}", pattern, err))
+                    .ok()
+            })
+            .collect();
+        Self {
+            apps: Config::option(&id, "ignore_apps")
+                .unwrap_or_else(|| DEFAULT_APPS.iter().map(|app| app.to_string()).collect()),
+            patterns,
+            concealed: Config::option(&id, "ignore_concealed").unwrap_or(true),
+            expire_seconds: Config::option(&id, "sensitive_expire_seconds").unwrap_or(0),
+        }
+    }
+    // Reads the frontmost application, and the clipboard types if concealed entries are ignored
+    pub fn source(&self) -> CopySource {
+        CopySource {
+            app: get_frontmost_application_data(),
+            types: if self.concealed {
+                clipboard_types()
+            } else {
+                vec![]
+            },
+        }
+    }
+    fn matches(&self, source: &CopySource, text: Option<&str>) -> bool {
+        if let Some(app) = &source.app {
+            if self.apps.iter().any(|ignored| {
+                ignored.eq_ignore_ascii_case(&app.id) || ignored.eq_ignore_ascii_case(&app.name)
+            }) {
+                return true;
+            }
+        }
+        if let Some(text) = text {
+            if self.patterns.iter().any(|pattern| pattern.is_match(text)) {
+                return true;
+            }
+        }
+        self.concealed
+            && source
+                .types
+                .iter()
+                .any(|kind| CONCEALED_TYPES.contains(&kind.as_str()))
+    }
+    // `text` is `None` for images, which are only checked by application and clipboard type
+    pub fn check(&self, source: &CopySource, text: Option<&str>) -> Sensitivity {
+        if !self.matches(source, text) {
+            return Sensitivity::None;
+        }
+        if self.expire_seconds == 0 {
+            return Sensitivity::Skip;
+        }
+        Timestamp::now()
+            .checked_add((self.expire_seconds as i64).seconds())
+            .map(Sensitivity::Expire)
+            .unwrap_or(Sensitivity::Skip)
+    }
+}
@@ -0,0 +1,394 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+// Conversions of copied text, pasted right away or copied back as a new clipboard entry
+
+use std::collections::HashSet;
+
+use base64::{
+    Engine,
+    engine::general_purpose::{STANDARD, URL_SAFE},
+};
+use gpui::{
+    AnyView, App, AppContext, ClipboardItem, Context, IntoElement, ParentElement, Render,
+    SharedString, Styled, Window, div,
+};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, percent_decode_str, utf8_percent_encode};
+
+use crate::{
+    command,
+    components::{
+        list::{Item, ItemBuilder, ListBuilder, ListItem},
+        shared::{Icon, Img},
+    },
+    state::{CommandTrait, LAction, Shortcut, StateItem, StateViewBuilder, StateViewContext},
+    theme::LTheme,
+};
+
+use crate::commands::paste;
+
+// Unreserved characters of RFC 3986 stay as they are
+const URL_SAFE_CHARS: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+#[derive(Clone, Copy)]
+enum Transform {
+    Upper,
+    Lower,
+    Title,
+    Snake,
+    Camel,
+    Trim,
+    JsonPretty,
+    JsonMinify,
+    Base64Encode,
+    Base64Decode,
+    UrlEncode,
+    UrlDecode,
+    SortLines,
+    DedupeLines,
+}
+
+const TRANSFORMS: [Transform; 14] = [
+    Transform::Upper,
+    Transform::Lower,
+    Transform::Title,
+    Transform::Snake,
+    Transform::Camel,
+    Transform::Trim,
+    Transform::JsonPretty,
+    Transform::JsonMinify,
+    Transform::Base64Encode,
+    Transform::Base64Decode,
+    Transform::UrlEncode,
+    Transform::UrlDecode,
+    Transform::SortLines,
+    Transform::DedupeLines,
+];
+
+// Lowercased words, split at anything that isn't alphanumeric and at case changes such as in "parseHTTPResponse"
+fn words(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut words = vec![];
+    let mut word = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+        let prev = i.checked_sub(1).map(|i| chars[i]);
+        let next = chars.get(i + 1);
+        let boundary = c.is_uppercase()
+            && prev.is_some_and(|prev| {
+                prev.is_lowercase()
+                    || prev.is_numeric()
+                    || (prev.is_uppercase() && next.is_some_and(|next| next.is_lowercase()))
+            });
+        if boundary && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        word.extend(c.to_lowercase());
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+// Whitespace is dropped outside of strings, `serde_json` would reorder the keys
+fn minify_json(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let (mut in_string, mut escaped) = (false, false);
+    for c in text.chars() {
+        if in_string {
+            out.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else if !c.is_whitespace() {
+            in_string = c == '"';
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn pretty_json(text: &str) -> String {
+    let chars: Vec<char> = minify_json(text).chars().collect();
+    let mut out = String::with_capacity(chars.len() * 2);
+    let mut depth = 0;
+    let (mut in_string, mut escaped) = (false, false);
+    let newline = |out: &mut String, depth: usize| {
+        out.push('\n');
+        out.push_str(&"  ".repeat(depth));
+    };
+    for (i, &c) in chars.iter().enumerate() {
+        if in_string {
+            out.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match c {
+            '"' => {
+                in_string = true;
+                out.push(c);
+            }
+            '{' | '[' => {
+                out.push(c);
+                // Empty objects and arrays stay on one line
+                if !matches!(chars.get(i + 1), Some('}' | ']')) {
+                    depth += 1;
+                    newline(&mut out, depth);
+                }
+            }
+            '}' | ']' => {
+                if !matches!(chars[i - 1], '{' | '[') {
+                    depth -= 1;
+                    newline(&mut out, depth);
+                }
+                out.push(c);
+            }
+            ',' => {
+                out.push(c);
+                newline(&mut out, depth);
+            }
+            ':' => out.push_str(": "),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+impl Transform {
+    fn label(&self) -> &'static str {
+        match self {
+            Transform::Upper => "UPPER CASE",
+            Transform::Lower => "lower case",
+            Transform::Title => "Title Case",
+            Transform::Snake => "snake_case",
+            Transform::Camel => "camelCase",
+            Transform::Trim => "Trim Whitespace",
+            Transform::JsonPretty => "Format JSON",
+            Transform::JsonMinify => "Minify JSON",
+            Transform::Base64Encode => "Base64 Encode",
+            Transform::Base64Decode => "Base64 Decode",
+            Transform::UrlEncode => "URL Encode",
+            Transform::UrlDecode => "URL Decode",
+            Transform::SortLines => "Sort Lines",
+            Transform::DedupeLines => "Remove Duplicate Lines",
+        }
+    }
+    fn icon(&self) -> Icon {
+        match self {
+            Transform::Upper
+            | Transform::Lower
+            | Transform::Title
+            | Transform::Snake
+            | Transform::Camel => Icon::CaseSensitive,
+            Transform::Trim => Icon::Scissors,
+            Transform::JsonPretty | Transform::JsonMinify => Icon::Braces,
+            Transform::Base64Encode
+            | Transform::Base64Decode
+            | Transform::UrlEncode
+            | Transform::UrlDecode => Icon::Binary,
+            Transform::SortLines | Transform::DedupeLines => Icon::ArrowDownAZ,
+        }
+    }
+    fn apply(&self, text: &str) -> anyhow::Result<String> {
+        Ok(match self {
+            Transform::Upper => text.to_uppercase(),
+            Transform::Lower => text.to_lowercase(),
+            Transform::Title => {
+                let mut out = String::with_capacity(text.len());
+                let mut start = true;
+                for c in text.chars() {
+                    if start {
+                        out.extend(c.to_uppercase());
+                    } else {
+                        out.extend(c.to_lowercase());
+                    }
+                    start = c.is_whitespace();
+                }
+                out
+            }
+            Transform::Snake => words(text).join("_"),
+            Transform::Camel => words(text)
+                .iter()
+                .enumerate()
+                .map(|(i, word)| {
+                    if i == 0 {
+                        word.clone()
+                    } else {
+                        capitalize(word)
+                    }
+                })
+                .collect(),
+            Transform::Trim => text.trim().to_string(),
+            Transform::JsonPretty => {
+                serde_json::from_str::<serde::de::IgnoredAny>(text)?;
+                pretty_json(text)
+            }
+            Transform::JsonMinify => {
+                serde_json::from_str::<serde::de::IgnoredAny>(text)?;
+                minify_json(text)
+            }
+            Transform::Base64Encode => STANDARD.encode(text),
+            Transform::Base64Decode => {
+                let text = text.trim();
+                let bytes = STANDARD.decode(text).or_else(|_| URL_SAFE.decode(text))?;
+                String::from_utf8(bytes)?
+            }
+            Transform::UrlEncode => utf8_percent_encode(text, URL_SAFE_CHARS).to_string(),
+            Transform::UrlDecode => percent_decode_str(text).decode_utf8()?.to_string(),
+            Transform::SortLines => {
+                let mut lines: Vec<&str> = text.lines().collect();
+                lines.sort_unstable();
+                lines.join("\n")
+            }
+            Transform::DedupeLines => {
+                let mut seen = HashSet::new();
+                text.lines()
+                    .filter(|line| seen.insert(*line))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+        })
+    }
+}
+
+fn transform_item(transform: Transform, result: String) -> Item {
+    ItemBuilder::new(
+        transform.label(),
+        ListItem::new(
+            Some(Img::default().icon(transform.icon())),
+            transform.label(),
+            None,
+            vec![],
+        ),
+    )
+    .actions(vec![
+        LAction::new(
+            Img::default().icon(Icon::ClipboardPaste),
+            "Paste",
+            None,
+            {
+                let result = result.clone();
+                move |_, cx| paste(&result, cx)
+            },
+            false,
+        ),
+        LAction::new(
+            Img::default().icon(Icon::Copy),
+            "Copy as New Entry",
+            Some(Shortcut::new("enter").shift()),
+            {
+                let result = result.clone();
+                move |actions, cx| {
+                    // Picked up by the clipboard watcher like any other copy
+                    cx.write_to_clipboard(ClipboardItem::new_string(result.clone()));
+                    actions.toast.success("Copied to clipboard", cx);
+                }
+            },
+            false,
+        ),
+    ])
+    .preview(0.66, {
+        let preview = TransformPreview {
+            text: result.into(),
+        };
+        move |cx| StateItem::init(preview.clone(), false, cx)
+    })
+    .build()
+}
+
+#[derive(Clone)]
+pub(super) struct TransformListBuilder {
+    pub(super) text: String,
+}
+command!(TransformListBuilder);
+
+impl StateViewBuilder for TransformListBuilder {
+    fn build(&self, context: &mut StateViewContext, window: &mut Window, cx: &mut App) -> AnyView {
+        context.query.set_placeholder("Transform text...", cx);
+        let text = self.text.clone();
+        ListBuilder::new()
+            .build(
+                move |_, _, _| {
+                    // Only what applies to the text and actually changes it is offered
+                    Ok(Some(
+                        TRANSFORMS
+                            .into_iter()
+                            .filter_map(|transform| {
+                                let result = transform.apply(&text).ok()?;
+                                (result != text).then(|| transform_item(transform, result))
+                            })
+                            .collect(),
+                    ))
+                },
+                context,
+                window,
+                cx,
+            )
+            .into()
+    }
+}
+
+#[derive(Clone)]
+struct TransformPreview {
+    text: SharedString,
+}
+
+impl Render for TransformPreview {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.global::<LTheme>();
+        div()
+            .ml_2()
+            .pl_2()
+            .border_l_1()
+            .border_color(theme.surface0)
+            .h_full()
+            .overflow_hidden()
+            .text_xs()
+            .font_family(theme.font_mono.clone())
+            .child(div().p_2().child(self.text.clone()))
+    }
+}
+
+command!(TransformPreview);
+
+impl StateViewBuilder for TransformPreview {
+    fn build(&self, _: &mut StateViewContext, _: &mut Window, cx: &mut App) -> AnyView {
+        cx.new(|_| self.clone()).into()
+    }
+}
@@ -0,0 +1,254 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+// In-memory index of everything below the configured roots, skipping whatever `.gitignore` and `.ignore` files exclude
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::{Duration, SystemTime},
+};
+
+use gpui::App;
+use ignore::WalkBuilder;
+use log::error;
+use notify::Watcher;
+use notify_debouncer_full::new_debouncer;
+use parking_lot::{RwLock, RwLockReadGuard};
+
+use crate::{config::Config, paths::paths, state::CommandTrait};
+
+use super::list::FilesCommandBuilder;
+
+const DEFAULT_ROOTS: [&str; 3] = ["~/Desktop", "~/Documents", "~/Downloads"];
+
+#[derive(Clone)]
+pub struct IndexEntry {
+    pub dir: bool,
+    pub modified: Option<SystemTime>,
+}
+
+#[derive(Clone, PartialEq)]
+struct IndexOptions {
+    roots: Vec<PathBuf>,
+    hidden: bool,
+}
+
+impl IndexOptions {
+    // `roots` and `hidden` under `[options."loungy::commands::files::list"]`
+    fn load() -> Self {
+        let id = FilesCommandBuilder.command();
+        let roots = Config::option::<Vec<String>>(&id, "roots")
+            .unwrap_or_else(|| DEFAULT_ROOTS.iter().map(|root| root.to_string()).collect())
+            .into_iter()
+            .map(|root| match root.strip_prefix("~/") {
+                Some(rest) => paths().home.join(rest),
+                None if root == "~" => paths().home.clone(),
+                None => PathBuf::from(root),
+            })
+            .collect();
+        Self {
+            roots,
+            hidden: Config::option::<bool>(&id, "hidden").unwrap_or(false),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct FileIndex {
+    pub entries: BTreeMap<PathBuf, IndexEntry>,
+    // Bumped on every change, so lists know when to rebuild their items
+    pub generation: u64,
+    options: Option<IndexOptions>,
+}
+
+fn store() -> &'static RwLock<FileIndex> {
+    static INDEX: OnceLock<RwLock<FileIndex>> = OnceLock::new();
+    INDEX.get_or_init(|| RwLock::new(FileIndex::default()))
+}
+
+pub fn index() -> RwLockReadGuard<'static, FileIndex> {
+    store().read()
+}
+
+// Everything below `path`, the walk picks up ignore files from parent folders as well
+fn walk(path: &Path, depth: Option<usize>, hidden: bool) -> Vec<(PathBuf, IndexEntry)> {
+    WalkBuilder::new(path)
+        .hidden(!hidden)
+        .require_git(false)
+        .max_depth(depth)
+        .build()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.depth() > 0)
+        .map(|entry| {
+            let meta = entry.metadata().ok();
+            let entry_data = IndexEntry {
+                dir: meta.as_ref().is_some_and(|meta| meta.is_dir()),
+                modified: meta.and_then(|meta| meta.modified().ok()),
+            };
+            (entry.into_path(), entry_data)
+        })
+        .collect()
+}
+
+impl FileIndex {
+    fn build(options: &IndexOptions) -> BTreeMap<PathBuf, IndexEntry> {
+        options
+            .roots
+            .iter()
+            .filter(|root| root.is_dir())
+            .flat_map(|root| walk(root, None, options.hidden))
+            .collect()
+    }
+    fn indexed(&self, dir: &Path) -> bool {
+        let Some(options) = &self.options else {
+            return false;
+        };
+        options.roots.iter().any(|root| root == dir)
+            || self.entries.get(dir).is_some_and(|entry| entry.dir)
+    }
+    // Re-lists a folder after something in it changed, folders that are ignored themselves are left alone.
+    // Only reads the index, so the walk can happen in the background while lists keep using it
+    fn changes(&self, dir: &Path, recursive: bool) -> Option<IndexChange> {
+        let hidden = self.options.as_ref()?.hidden;
+        if !self.indexed(dir) {
+            return None;
+        }
+        let mut change = IndexChange {
+            dir: dir.to_path_buf(),
+            recursive,
+            removed: vec![],
+            added: vec![],
+        };
+        if recursive {
+            change.added = walk(dir, None, hidden);
+            return Some(change);
+        }
+        let listing = walk(dir, Some(1), hidden);
+        let listed: BTreeSet<&PathBuf> = listing.iter().map(|(path, _)| path).collect();
+        change.removed = self
+            .entries
+            .keys()
+            .filter(|path| path.parent() == Some(dir) && !listed.contains(path))
+            .cloned()
+            .collect();
+        for (path, entry) in &listing {
+            let known = self.entries.get(path).is_some_and(|known| known.dir);
+            if entry.dir && !known {
+                // New or previously ignored folder, its contents weren't indexed yet
+                change.added.extend(walk(path, None, hidden));
+            }
+        }
+        change.added.extend(listing);
+        Some(change)
+    }
+    fn apply(&mut self, change: IndexChange) {
+        if change.recursive {
+            self.entries
+                .retain(|path, _| path == &change.dir || !path.starts_with(&change.dir));
+        }
+        for path in change.removed {
+            self.entries.retain(|entry, _| !entry.starts_with(&path));
+        }
+        self.entries.extend(change.added);
+        self.generation += 1;
+    }
+}
+
+// What a refresh of a folder found, applied to the index in one go
+struct IndexChange {
+    dir: PathBuf,
+    // Everything below `dir` is replaced, not just its direct children
+    recursive: bool,
+    // Gone along with everything below them
+    removed: Vec<PathBuf>,
+    added: Vec<(PathBuf, IndexEntry)>,
+}
+
+// Builds the index in the background and keeps it current, rebuilding it whenever the roots in the config change
+pub fn watch(cx: &mut App) {
+    cx.spawn(async move |cx| {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut debouncer = match new_debouncer(Duration::from_secs(1), None, tx) {
+            Ok(debouncer) => debouncer,
+            Err(err) => {
+                error!("Failed to watch files: {:?}", err);
+                return;
+            }
+        };
+        loop {
+            let options = IndexOptions::load();
+            let current = store().read().options.clone();
+            if current.as_ref() != Some(&options) {
+                if let Some(current) = current {
+                    for root in current.roots {
+                        let _ = debouncer.watcher().unwatch(&root);
+                    }
+                }
+                for root in options.roots.iter().filter(|root| root.is_dir()) {
+                    let _ = debouncer
+                        .watcher()
+                        .watch(root, notify::RecursiveMode::Recursive);
+                }
+                let entries = {
+                    let options = options.clone();
+                    cx.background_executor()
+                        .spawn(async move { FileIndex::build(&options) })
+                        .await
+                };
+                let mut index = store().write();
+                index.entries = entries;
+                index.options = Some(options);
+                index.generation += 1;
+            }
+
+            if let Ok(Ok(events)) = rx.try_recv() {
+                let mut dirs = BTreeMap::<PathBuf, bool>::new();
+                for path in events.iter().flat_map(|event| event.paths.iter()) {
+                    let Some(parent) = path.parent() else {
+                        continue;
+                    };
+                    // Changed ignore rules can hide or reveal anything below them
+                    let ignore_file = path
+                        .file_name()
+                        .is_some_and(|name| name == ".gitignore" || name == ".ignore");
+                    *dirs.entry(parent.to_path_buf()).or_default() |= ignore_file;
+                }
+                let changes = cx
+                    .background_executor()
+                    .spawn(async move {
+                        let index = store().read();
+                        let mut done = BTreeSet::new();
+                        let mut changes = vec![];
+                        for (dir, recursive) in dirs {
+                            if done.iter().any(|parent: &PathBuf| dir.starts_with(parent)) {
+                                continue;
+                            }
+                            changes.extend(index.changes(&dir, recursive));
+                            if recursive {
+                                done.insert(dir);
+                            }
+                        }
+                        changes
+                    })
+                    .await;
+                // Nothing else writes to the index, so it's still what the changes were worked out against
+                let mut index = store().write();
+                for change in changes {
+                    index.apply(change);
+                }
+            }
+            cx.background_executor().timer(Duration::from_secs(1)).await;
+        }
+    })
+    .detach();
+}
@@ -0,0 +1,398 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+use std::{
+    cell::{Cell, RefCell},
+    cmp::Reverse,
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::Duration,
+};
+
+use gpui::{
+    AnyView, App, AppContext, ClipboardItem, Context, FontWeight, IntoElement, ObjectFit,
+    ParentElement, Render, SharedString, Styled, StyledImage, Window, div, img,
+};
+use jiff::Timestamp;
+use nucleo::{Matcher, Utf32Str, pattern::Atom};
+
+use crate::{
+    command,
+    commands::{RootCommand, RootCommandBuilder, process::list::format_bytes},
+    components::{
+        list::{
+            Item, ItemBuilder, ListBuilder, ListItem,
+            nucleo::{Score, fuzzy_match},
+        },
+        shared::{Icon, Img},
+    },
+    date::format_date,
+    paths::paths,
+    platform::close_and_paste_file,
+    state::{
+        CommandTrait, LAction, Shortcut, StateItem, StateModel, StateViewBuilder, StateViewContext,
+    },
+    theme::LTheme,
+    window::LWindow,
+};
+
+use super::index::{IndexEntry, index, watch};
+
+// Without a query only the most recently modified files are listed
+const RECENT_LIMIT: usize = 50;
+const MATCH_LIMIT: usize = 200;
+// Text previews only read the start of the file
+const PREVIEW_BYTES: u64 = 16 * 1024;
+const IMAGE_EXTENSIONS: [&str; 9] = [
+    "png", "jpg", "jpeg", "gif", "webp", "bmp", "svg", "tif", "tiff",
+];
+
+// Paths below the home folder are shown and matched as `~/...`
+fn display_path(path: &Path) -> String {
+    match path.strip_prefix(&paths().home) {
+        Ok(rest) => format!("~/{}", rest.to_string_lossy()),
+        Err(_) => path.to_string_lossy().to_string(),
+    }
+}
+
+fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+fn file_item(path: &Path, entry: &IndexEntry) -> Item {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| display_path(path));
+    let icon = if entry.dir {
+        Icon::Folder
+    } else if is_image(path) {
+        Icon::Image
+    } else {
+        Icon::File
+    };
+    let mut actions = vec![
+        LAction::new(
+            Img::default().icon(Icon::ArrowUpRightFromSquare),
+            "Open",
+            None,
+            {
+                let path = path.to_path_buf();
+                move |_, cx| {
+                    cx.open_with_system(&path);
+                    LWindow::close(cx);
+                }
+            },
+            false,
+        ),
+        LAction::new(
+            Img::default().icon(Icon::FolderOpen),
+            "Reveal in File Manager",
+            Some(Shortcut::new("enter").shift()),
+            {
+                let path = path.to_path_buf();
+                move |_, cx| {
+                    cx.reveal_path(&path);
+                    LWindow::close(cx);
+                }
+            },
+            false,
+        ),
+        LAction::new(
+            Img::default().icon(Icon::Copy),
+            "Copy Path",
+            Some(Shortcut::new("c").cmd().shift()),
+            {
+                let path = path.to_string_lossy().to_string();
+                move |actions, cx| {
+                    cx.write_to_clipboard(ClipboardItem::new_string(path.clone()));
+                    actions.toast.success("Copied path to clipboard", cx);
+                }
+            },
+            false,
+        ),
+    ];
+    if !entry.dir {
+        actions.push(LAction::new(
+            Img::default().icon(Icon::ClipboardPaste),
+            "Paste File",
+            None,
+            {
+                let path = path.to_path_buf();
+                move |_, cx| {
+                    #[cfg(target_os = "windows")]
+                    if let Some(handle) = cx.windows().first().copied() {
+                        let _ = cx.update_window(handle, |_, window, cx| {
+                            close_and_paste_file(&path, window, cx);
+                        });
+                    }
+                    #[cfg(not(target_os = "windows"))]
+                    close_and_paste_file(&path, cx);
+                }
+            },
+            false,
+        ));
+    }
+    ItemBuilder::new(
+        path.to_path_buf(),
+        ListItem::new(
+            Some(Img::default().icon(icon)),
+            name,
+            path.parent().map(display_path),
+            vec![],
+        ),
+    )
+    .keywords(vec![display_path(path)])
+    .actions(actions)
+    .preview(0.5, {
+        let preview = FilePreview::new(path, entry);
+        move |cx| StateItem::init(preview.clone(), false, cx)
+    })
+    .build()
+}
+
+// Indexed files are only matched by path, items are built for the ones actually listed
+struct IndexedFile {
+    path: PathBuf,
+    entry: IndexEntry,
+    keyword: String,
+}
+
+impl Score for &IndexedFile {
+    fn score(
+        &self,
+        pattern: &Atom,
+        buf: &mut Vec<char>,
+        matcher: &mut Matcher,
+    ) -> Option<(Self, u16)> {
+        pattern
+            .score(Utf32Str::new(&self.keyword, buf), matcher)
+            .map(|score| (*self, score))
+    }
+}
+
+#[derive(Clone)]
+pub struct FilesListBuilder;
+command!(FilesListBuilder);
+
+impl StateViewBuilder for FilesListBuilder {
+    fn build(&self, context: &mut StateViewContext, window: &mut Window, cx: &mut App) -> AnyView {
+        context.query.set_placeholder("Search files...", cx);
+        let generation = Rc::new(Cell::new(None));
+        let files: Rc<RefCell<Vec<IndexedFile>>> = Rc::default();
+        ListBuilder::new()
+            .interval(Duration::from_secs(1))
+            .filter({
+                let files = files.clone();
+                move |this, cx| {
+                    let query = this.query.get_text(cx);
+                    let files = files.borrow();
+                    let mut matches: Vec<&IndexedFile> = if query.trim().is_empty() {
+                        files.iter().take(RECENT_LIMIT).collect()
+                    } else {
+                        fuzzy_match(&query, files.iter().collect(), true)
+                    };
+                    matches.truncate(MATCH_LIMIT);
+                    matches
+                        .into_iter()
+                        .map(|file| file_item(&file.path, &file.entry))
+                        .collect()
+                }
+            })
+            .build(
+                move |_, _, _| {
+                    let index = index();
+                    // Only re-read the index once it actually changed
+                    if generation.get() == Some(index.generation) {
+                        return Ok(None);
+                    }
+                    generation.set(Some(index.generation));
+                    let mut entries: Vec<IndexedFile> = index
+                        .entries
+                        .iter()
+                        .map(|(path, entry)| IndexedFile {
+                            path: path.clone(),
+                            entry: entry.clone(),
+                            keyword: display_path(path),
+                        })
+                        .collect();
+                    entries.sort_by_key(|file| Reverse(file.entry.modified));
+                    *files.borrow_mut() = entries;
+                    // The filter builds the items from the files
+                    Ok(Some(vec![]))
+                },
+                context,
+                window,
+                cx,
+            )
+            .into()
+    }
+}
+
+#[derive(Clone)]
+enum FileContents {
+    Text(SharedString),
+    Image(PathBuf),
+    None,
+}
+
+#[derive(Clone)]
+struct FilePreview {
+    path: PathBuf,
+    dir: bool,
+    modified: Option<Timestamp>,
+    size: Option<u64>,
+    contents: FileContents,
+}
+
+impl FilePreview {
+    fn new(path: &Path, entry: &IndexEntry) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            dir: entry.dir,
+            modified: entry
+                .modified
+                .and_then(|modified| Timestamp::try_from(modified).ok()),
+            size: None,
+            contents: FileContents::None,
+        }
+    }
+    // Reads the file only once the preview is actually shown
+    fn load(mut self) -> Self {
+        if self.dir {
+            return self;
+        }
+        self.size = self.path.metadata().ok().map(|meta| meta.len());
+        if is_image(&self.path) {
+            self.contents = FileContents::Image(self.path.clone());
+            return self;
+        }
+        let mut bytes = vec![];
+        if File::open(&self.path)
+            .and_then(|file| file.take(PREVIEW_BYTES).read_to_end(&mut bytes))
+            .is_err()
+            || bytes.contains(&0)
+        {
+            return self;
+        }
+        let text = match std::str::from_utf8(&bytes) {
+            Ok(text) => text,
+            // Cut off in the middle of a character
+            Err(err) if err.error_len().is_none() => {
+                std::str::from_utf8(&bytes[..err.valid_up_to()]).unwrap_or_default()
+            }
+            Err(_) => return self,
+        };
+        self.contents = FileContents::Text(text.to_string().into());
+        self
+    }
+}
+
+impl Render for FilePreview {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.global::<LTheme>();
+        let kind = match (&self.contents, self.dir) {
+            (_, true) => "Folder",
+            (FileContents::Image(_), _) => "Image",
+            (FileContents::Text(_), _) => "Text",
+            (FileContents::None, _) => "File",
+        };
+        let mut table = vec![
+            ("Kind".to_string(), kind.to_string()),
+            ("Where".to_string(), display_path(&self.path)),
+        ];
+        if let Some(size) = self.size {
+            table.push(("Size".to_string(), format_bytes(size)));
+        }
+        if let Some(modified) = self.modified {
+            table.push(("Modified".to_string(), format_date(modified, cx)));
+        }
+        div()
+            .ml_2()
+            .pl_2()
+            .border_l_1()
+            .border_color(theme.surface0)
+            .h_full()
+            .flex()
+            .flex_col()
+            .justify_between()
+            .text_xs()
+            .child(
+                div()
+                    .flex_1()
+                    .overflow_hidden()
+                    .child(match &self.contents {
+                        FileContents::Text(text) => div()
+                            .p_2()
+                            .font_family(theme.font_mono.clone())
+                            .child(text.clone())
+                            .into_any_element(),
+                        FileContents::Image(path) => img(path.clone())
+                            .size_full()
+                            .object_fit(ObjectFit::Contain)
+                            .into_any_element(),
+                        FileContents::None => div().into_any_element(),
+                    }),
+            )
+            .child(
+                div()
+                    .border_t_1()
+                    .border_color(theme.surface0)
+                    .mt_auto()
+                    .p_2()
+                    .children(table.into_iter().map(|(key, value)| {
+                        div()
+                            .flex()
+                            .justify_between()
+                            .child(
+                                div()
+                                    .font_weight(FontWeight::SEMIBOLD)
+                                    .text_color(theme.subtext0)
+                                    .child(key),
+                            )
+                            .child(div().child(value).font_family(theme.font_mono.clone()))
+                    })),
+            )
+    }
+}
+
+command!(FilePreview);
+
+impl StateViewBuilder for FilePreview {
+    fn build(&self, _: &mut StateViewContext, _: &mut Window, cx: &mut App) -> AnyView {
+        let preview = self.clone().load();
+        cx.new(|_| preview).into()
+    }
+}
+
+pub struct FilesCommandBuilder;
+command!(FilesCommandBuilder);
+
+impl RootCommandBuilder for FilesCommandBuilder {
+    fn build(&self, window: &mut Window, cx: &mut App) -> RootCommand {
+        watch(cx);
+        RootCommand::new(
+            "files",
+            "Search Files",
+            "Files",
+            Icon::FolderSearch,
+            vec!["Find", "Documents", "Folder"],
+            None,
+            |_, cx| {
+                StateModel::update(|this, cx| this.push(FilesListBuilder, window, cx), cx);
+            },
+        )
+    }
+}
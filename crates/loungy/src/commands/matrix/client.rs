@@ -25,6 +25,7 @@ use rand::Rng;
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    archive::Archived,
     db::LDb,
     paths::{NAME, paths},
     state::{LActions, StateModel},
@@ -32,13 +33,20 @@ use crate::{
 
 #[derive(Debug, Serialize, Deserialize, Collection, Clone)]
 #[collection(name = "matrix.sessions")]
-pub(super) struct Session {
+pub(crate) struct Session {
     #[natural_id]
     pub id: String,
     inner: MatrixSession,
     passphrase: String,
 }
 
+// Holds the access token, so there is nothing left to keep without secrets
+impl Archived for Session {
+    fn without_secrets(self) -> Option<Self> {
+        None
+    }
+}
+
 pub fn db() -> &'static Database {
     static DB: OnceLock<Database> = OnceLock::new();
     DB.get_or_init(LDb::init_collection::<Session>)
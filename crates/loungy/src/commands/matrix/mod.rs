@@ -11,7 +11,7 @@
 
 mod account;
 mod chat;
-mod client;
+pub(super) mod client;
 mod compose;
 pub mod list;
 mod mxc;
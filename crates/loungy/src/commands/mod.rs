@@ -9,36 +9,44 @@
  *
  */
 
+#[cfg(target_os = "windows")]
+use gpui::AppContext;
 use gpui::{AnyView, App, Global, Window};
 use log::error;
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, rc::Rc};
 
 use crate::{
+    archive::ArchivedCollection,
     command,
     components::{
         form::{Form, Input, InputKind},
         list::{Accessory, Item, ItemBuilder, ListItem},
         shared::{Icon, Img},
     },
-    hotkey::HotkeyManager,
+    hotkey::{CommandAliases, HotkeyManager},
+    platform::close_and_paste,
     state::{
         ActionFn, CommandTrait, LAction, Shortcut, StateModel, StateViewBuilder, StateViewContext,
     },
 };
 
-use self::root::list;
+use self::root::{frecency::Frecency, list, preferences::Preferences};
 
 #[cfg(feature = "bitwarden")]
 mod bitwarden;
 #[cfg(feature = "clipboard")]
 mod clipboard;
+mod files;
 #[cfg(feature = "matrix")]
 mod matrix;
 #[cfg(target_os = "macos")]
 mod menu;
 mod process;
 pub mod root;
+mod script;
+#[cfg(feature = "clipboard")]
+mod snippets;
 #[cfg(feature = "tailscale")]
 mod tailscale;
 mod theme;
@@ -47,10 +55,22 @@ fn def() -> Rc<dyn ActionFn> {
     Rc::new(|_, _| {})
 }
 
+// Closes the window and pastes the text into the application focused before, from within an action
+fn paste(text: &str, cx: &mut App) {
+    #[cfg(target_os = "windows")]
+    if let Some(handle) = cx.windows().first().copied() {
+        let _ = cx.update_window(handle, |_, window, cx| {
+            close_and_paste(text, false, window, cx);
+        });
+    }
+    #[cfg(not(target_os = "windows"))]
+    close_and_paste(text, false, cx);
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct RootCommand {
     pub id: String,
-    title: String,
+    pub title: String,
     subtitle: String,
     icon: Icon,
     keywords: Vec<String>,
@@ -94,9 +114,11 @@ impl RootCommands {
     pub fn init(window: &mut Window, cx: &mut App) {
         let commands: Vec<Box<dyn RootCommandBuilder>> = vec![
             Box::new(list::LoungyCommandBuilder),
+            Box::new(root::frecency::ResetRankingCommandBuilder),
             #[cfg(target_os = "macos")]
             Box::new(menu::list::MenuCommandBuilder),
             Box::new(process::list::ProcessCommandBuilder),
+            Box::new(files::list::FilesCommandBuilder),
             Box::new(theme::list::ThemeCommandBuilder),
             #[cfg(feature = "tailscale")]
             Box::new(tailscale::list::TailscaleCommandBuilder),
@@ -106,6 +128,8 @@ impl RootCommands {
             Box::new(matrix::list::MatrixCommandBuilder),
             #[cfg(feature = "clipboard")]
             Box::new(clipboard::list::ClipboardCommandBuilder),
+            #[cfg(feature = "clipboard")]
+            Box::new(snippets::list::SnippetsCommandBuilder),
         ];
         let mut map = HashMap::new();
         for command in commands {
@@ -114,36 +138,58 @@ impl RootCommands {
             command.id = id.clone();
             map.insert(id, command);
         }
+        for command in script::list::script_commands(window) {
+            if map.contains_key(&command.id) {
+                error!("Script command id already in use: {}", command.id);
+                continue;
+            }
+            map.insert(command.id.clone(), command);
+        }
+        #[cfg(feature = "clipboard")]
+        snippets::list::insert_snippet_commands(&mut map);
         cx.set_global(Self { commands: map });
+        script::list::watch(window, cx);
     }
     pub fn list(window: &mut Window, cx: &mut App) -> Vec<Item> {
         let commands = cx.global::<Self>().commands.clone();
+        let aliases = CommandAliases::all_aliases();
         let items: Vec<Item> = commands
             .values()
+            .filter(|command| Preferences::is_enabled(&command.id))
             .map(|command| {
                 let mut keywords = vec![command.title.clone(), command.subtitle.clone()];
                 keywords.append(&mut command.keywords.clone());
+                let mut accessories = vec![];
+                if let Some(alias) = aliases.get(&command.id) {
+                    accessories.push(Accessory::new(alias, Some(Img::default().icon(Icon::Tag))));
+                }
+                accessories.push(
+                    command
+                        .shortcut
+                        .clone()
+                        .map(Accessory::shortcut)
+                        .unwrap_or(Accessory::new("Command", None)),
+                );
                 ItemBuilder::new(
                     command.id.clone(),
                     ListItem::new(
                         Some(Img::default().icon(command.icon.clone())),
                         command.title.clone(),
                         Some(command.subtitle.clone()),
-                        command
-                            .shortcut
-                            .clone()
-                            .map(|shortcut| vec![Accessory::shortcut(shortcut)])
-                            .unwrap_or(vec![Accessory::new("Command", None)]),
+                        accessories,
                     ),
                 )
                 .keywords(keywords)
                 .actions(vec![
-                    LAction::new_rc(
-                        Img::default().icon(command.icon.clone()),
-                        command.title.clone(),
-                        None,
-                        command.action.clone(),
-                        false,
+                    Frecency::track(
+                        command.id.clone(),
+                        LAction::new_rc(
+                            Img::default().icon(command.icon.clone()),
+                            command.title.clone(),
+                            None,
+                            command.action.clone(),
+                            false,
+                        ),
                     ),
                     LAction::new(
                         Img::default().icon(Icon::Keyboard),
@@ -161,6 +207,23 @@ impl RootCommands {
                         },
                         false,
                     ),
+                    LAction::new(
+                        Img::default().icon(Icon::Tag),
+                        "Change Alias",
+                        None,
+                        {
+                            let id = command.id.clone();
+                            move |_, cx| {
+                                let id = id.clone();
+                                StateModel::update(
+                                    |this, cx| this.push(AliasBuilder { id }, window, cx),
+                                    cx,
+                                );
+                            }
+                        },
+                        false,
+                    ),
+                    Frecency::clear_action(command.id.clone()),
                 ])
                 .weight(3)
                 .build()
@@ -172,6 +235,35 @@ impl RootCommands {
 
 impl Global for RootCommands {}
 
+// Collections owned by the commands, see `archive`
+pub fn archived_collections() -> Vec<ArchivedCollection> {
+    vec![
+        ArchivedCollection::new::<root::frecency::LaunchHistory>(root::frecency::db),
+        ArchivedCollection::new::<root::numbat::NumbatHistory>(root::numbat::db),
+        #[cfg(feature = "clipboard")]
+        ArchivedCollection::new::<clipboard::list::ClipboardListItem>(clipboard::list::db_items),
+        #[cfg(feature = "clipboard")]
+        ArchivedCollection::new::<clipboard::list::ClipboardDetail>(clipboard::list::db_detail),
+        #[cfg(feature = "clipboard")]
+        ArchivedCollection::new::<snippets::list::Snippet>(snippets::list::db),
+        #[cfg(feature = "bitwarden")]
+        ArchivedCollection::new::<bitwarden::list::BitwardenAccount>(bitwarden::list::db),
+        #[cfg(feature = "matrix")]
+        ArchivedCollection::new::<matrix::client::Session>(matrix::client::db),
+    ]
+}
+
+// Reloads whatever the commands keep in memory after an archive was imported
+pub fn reload_archived(cx: &mut App) {
+    Frecency::init(cx);
+    CommandAliases::reload(cx);
+    #[cfg(feature = "clipboard")]
+    {
+        snippets::list::refresh(cx);
+        clipboard::list::ClipboardHistory::reload(cx);
+    }
+}
+
 #[derive(Clone)]
 pub struct HotkeyBuilder {
     id: String,
@@ -180,7 +272,7 @@ command!(HotkeyBuilder);
 impl StateViewBuilder for HotkeyBuilder {
     fn build(&self, context: &mut StateViewContext, window: &mut Window, cx: &mut App) -> AnyView {
         let id = self.id.clone();
-        let value = HotkeyManager::get(&id).map(Shortcut::new);
+        let value = HotkeyManager::get(&id).map(|keystroke| Shortcut::from(&keystroke));
         Form::new(
             vec![Input::new(
                 "hotkey",
@@ -196,7 +288,8 @@ impl StateViewBuilder for HotkeyBuilder {
                 if let Some(shortcut) = shortcut {
                     if let Err(err) = HotkeyManager::set(&id, shortcut.get(), cx) {
                         error!("Failed to set hotkey: {}", err);
-                        actions.toast.error("Failed to set hotkey", cx);
+                        // Conflicts and rejected keys are actionable, so show the reason
+                        actions.toast.error(err.to_string(), cx);
                     } else {
                         actions.toast.success("Hotkey set", cx);
                     }
@@ -220,3 +313,50 @@ impl StateViewBuilder for HotkeyBuilder {
         .into()
     }
 }
+
+#[derive(Clone)]
+pub struct AliasBuilder {
+    id: String,
+}
+command!(AliasBuilder);
+impl StateViewBuilder for AliasBuilder {
+    fn build(&self, context: &mut StateViewContext, _: &mut Window, cx: &mut App) -> AnyView {
+        let id = self.id.clone();
+        Form::new(
+            vec![Input::new(
+                "alias",
+                "Alias",
+                InputKind::TextField {
+                    placeholder: "Prefix such as \"cb\", leave empty to remove".to_string(),
+                    value: CommandAliases::alias(&id).unwrap_or_default(),
+                    validate: Some(|v| {
+                        v.trim()
+                            .contains(char::is_whitespace)
+                            .then_some("Alias must be a single word")
+                    }),
+                    password: false,
+                },
+                cx,
+            )],
+            move |values, actions, cx| {
+                let alias = values["alias"].value::<String>();
+                if alias.trim().is_empty() {
+                    if let Err(err) = CommandAliases::unset_alias(&id, cx) {
+                        error!("Failed to unset alias: {}", err);
+                        actions.toast.error("Failed to unset alias", cx);
+                    } else {
+                        actions.toast.success("Alias unset", cx);
+                    }
+                } else if let Err(err) = CommandAliases::set_alias(&id, &alias, cx) {
+                    error!("Failed to set alias: {}", err);
+                    actions.toast.error(err, cx);
+                } else {
+                    actions.toast.success("Alias set", cx);
+                }
+            },
+            context,
+            cx,
+        )
+        .into()
+    }
+}
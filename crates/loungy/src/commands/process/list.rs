@@ -54,7 +54,7 @@ impl Process {
     }
 }
 
-fn format_bytes(bytes: u64) -> String {
+pub(crate) fn format_bytes(bytes: u64) -> String {
     let kb = bytes / 1000;
     let mb = kb / 1000;
     let gb = mb as f32 / 1000.0;
@@ -147,6 +147,7 @@ impl StateViewBuilder for ProcessListBuilder {
                                         icon_path: PathBuf::new(),
                                         keywords: vec![],
                                         tag: "".to_string(),
+                                        actions: vec![],
                                     });
                                 ItemBuilder::new(p.pid, {
                                     let (m, c) = if sort_by_cpu {
@@ -0,0 +1,189 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+use std::{collections::HashMap, rc::Rc, sync::OnceLock};
+
+use bonsaidb::{
+    core::schema::{Collection, SerializedCollection},
+    local::Database,
+};
+use gpui::{App, BorrowAppContext, Global, Window};
+use jiff::Timestamp;
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    archive::Archived,
+    commands::{RootCommand, RootCommandBuilder},
+    components::{
+        list::item_id,
+        shared::{Icon, Img},
+    },
+    db::LDb,
+    state::{CommandTrait, LAction},
+};
+
+// Launches lose half their weight after a week
+const HALF_LIFE: f64 = 7.0 * 24.0 * 60.0 * 60.0;
+// How much a fully "hot" item can boost the fuzzy score
+const BOOST: f64 = 0.5;
+
+pub(crate) fn db() -> &'static Database {
+    static DB: OnceLock<Database> = OnceLock::new();
+    DB.get_or_init(LDb::init_collection::<LaunchHistory>)
+}
+
+#[derive(Serialize, Deserialize, Collection, Debug, Clone)]
+#[collection(name = "root.launch-history")]
+pub struct LaunchHistory {
+    #[natural_id]
+    id: String,
+    count: u32,
+    // Decayed launch count as of `last`
+    score: f64,
+    last: Timestamp,
+}
+
+impl Archived for LaunchHistory {}
+
+impl LaunchHistory {
+    fn decayed(&self, now: Timestamp) -> f64 {
+        let elapsed = (now.as_second() - self.last.as_second()).max(0) as f64;
+        self.score * 0.5f64.powf(elapsed / HALF_LIFE)
+    }
+}
+
+// Launch history of the root list items, keyed by their list item id
+pub struct Frecency {
+    history: HashMap<u64, LaunchHistory>,
+}
+
+impl Global for Frecency {}
+
+impl Frecency {
+    pub fn init(cx: &mut App) {
+        let history = LaunchHistory::all(db())
+            .query()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|doc| (item_id(&doc.contents.id), doc.contents))
+            .collect();
+        cx.set_global(Self { history });
+    }
+    pub fn record(id: &str, cx: &mut App) {
+        let now = Timestamp::now();
+        let key = item_id(id);
+        let history = cx.global::<Self>().history.get(&key).cloned();
+        let history = match history {
+            Some(history) => LaunchHistory {
+                count: history.count + 1,
+                score: history.decayed(now) + 1.0,
+                last: now,
+                ..history
+            },
+            None => LaunchHistory {
+                id: id.to_string(),
+                count: 1,
+                score: 1.0,
+                last: now,
+            },
+        };
+        if let Err(err) = history.clone().overwrite_into(&history.id, db()) {
+            error!("Failed to save launch history: {:?}", err);
+        }
+        cx.update_global::<Self, _>(|this, _| {
+            this.history.insert(key, history);
+        });
+    }
+    pub fn clear(id: &str, cx: &mut App) -> anyhow::Result<()> {
+        if let Some(history) = LaunchHistory::get(&id.to_string(), db())? {
+            history.delete(db())?;
+        }
+        cx.update_global::<Self, _>(|this, _| {
+            this.history.remove(&item_id(id));
+        });
+        Ok(())
+    }
+    pub fn reset(cx: &mut App) -> anyhow::Result<()> {
+        for history in LaunchHistory::all(db()).query()? {
+            history.delete(db())?;
+        }
+        cx.update_global::<Self, _>(|this, _| {
+            this.history.clear();
+        });
+        Ok(())
+    }
+    // Multiplier in `1.0..1.0 + BOOST`, approaching the upper bound for items launched often and recently
+    pub fn boost(&self, id: u64, now: Timestamp) -> f64 {
+        let Some(history) = self.history.get(&id) else {
+            return 1.0;
+        };
+        let score = history.decayed(now);
+        1.0 + BOOST * score / (score + 1.0)
+    }
+    // Records a launch whenever the action runs
+    pub fn track(id: impl ToString, action: LAction) -> LAction {
+        let id = id.to_string();
+        let inner = action.action.clone();
+        LAction {
+            action: Rc::new(move |actions, cx| {
+                Self::record(&id, cx);
+                inner(actions, cx);
+            }),
+            ..action
+        }
+    }
+    pub fn clear_action(id: impl ToString) -> LAction {
+        let id = id.to_string();
+        LAction::new(
+            Img::default().icon(Icon::History),
+            "Clear History",
+            None,
+            move |actions, cx| {
+                if let Err(err) = Self::clear(&id, cx) {
+                    error!("Failed to clear launch history: {:?}", err);
+                    actions.toast.error("Failed to clear history", cx);
+                } else {
+                    actions.toast.success("History cleared", cx);
+                }
+            },
+            false,
+        )
+    }
+}
+
+pub struct ResetRankingCommandBuilder;
+// `command!` would name it after this module, which the CLI would confuse with the root list
+impl CommandTrait for ResetRankingCommandBuilder {
+    fn command(&self) -> String {
+        "loungy::commands::ranking::reset".to_string()
+    }
+}
+impl RootCommandBuilder for ResetRankingCommandBuilder {
+    fn build(&self, _: &mut Window, _: &mut App) -> RootCommand {
+        RootCommand::new(
+            "reset-ranking",
+            "Reset Ranking",
+            "Loungy",
+            Icon::History,
+            vec!["Frecency", "History"],
+            None,
+            |actions, cx| {
+                if let Err(err) = Frecency::reset(cx) {
+                    error!("Failed to reset ranking: {:?}", err);
+                    actions.toast.error("Failed to reset ranking", cx);
+                } else {
+                    actions.toast.success("Ranking reset", cx);
+                }
+            },
+        )
+    }
+}
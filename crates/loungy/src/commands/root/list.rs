@@ -9,19 +9,33 @@
  *
  */
 
-use super::numbat::{Numbat, NumbatWrapper};
+use super::{
+    frecency::Frecency,
+    numbat::{Numbat, NumbatWrapper},
+    preferences::PreferencesBuilder,
+};
+#[cfg(target_os = "linux")]
+use crate::platform::launch_application;
 use crate::{
     command,
     commands::{RootCommand, RootCommandBuilder, RootCommands},
     components::{
-        list::{nucleo::fuzzy_match, Accessory, Item, ItemBuilder, ListBuilder, ListItem},
+        list::{
+            nucleo::{fuzzy_match, fuzzy_score},
+            Accessory, Item, ItemBuilder, ListBuilder, ListItem,
+        },
         shared::{Icon, Img},
     },
-    platform::{get_application_data, get_application_files, get_application_folders},
-    state::{CommandTrait, LAction, StateViewBuilder, StateViewContext},
+    hotkey::CommandAliases,
+    platform::{get_application_folders, get_applications},
+    query::TextEvent,
+    state::{CommandTrait, LAction, LActions, StateModel, StateViewBuilder, StateViewContext},
     window::LWindow,
 };
-use gpui::{AnyView, App, ClipboardItem, Window};
+use gpui::{AnyView, App, AppContext, Window};
+use jiff::Timestamp;
+#[cfg(target_os = "linux")]
+use log::error;
 use notify::Watcher;
 use notify_debouncer_full::new_debouncer;
 use std::{collections::HashMap, time::Duration};
@@ -35,78 +49,97 @@ impl StateViewBuilder for RootListBuilder {
             .query
             .set_placeholder("Search for apps and commands...", cx);
         let numbat = Numbat::init(&context.query, cx);
-        let commands = RootCommands::list(window, cx);
+        let commands = cx.new(|cx| RootCommands::list(window, cx));
+        let commands_clone = commands.clone();
 
         let list = ListBuilder::new()
             .filter(move |this, cx| {
                 let mut items = this.items_all.clone();
-                items.append(&mut commands.clone());
+                items.append(&mut commands_clone.read(cx).clone());
                 let query = this.query.view.upgrade();
                 if query.is_none() {
                     return vec![];
                 }
                 let query = query.unwrap().read(cx).text.clone();
-                let mut items = fuzzy_match(&query, items, false);
+                let numbat_item = |cx: &App| {
+                    numbat.read(cx).result.clone().map(|result| {
+                        ItemBuilder::new(
+                            "Numbat",
+                            NumbatWrapper {
+                                inner: numbat.clone(),
+                            },
+                        )
+                        .actions(Numbat::actions(&result, &numbat))
+                        .build()
+                    })
+                };
+                // `=` lists the calculator history, below the current result
+                if let Some(rest) = query.strip_prefix('=') {
+                    let mut items: Vec<Item> = numbat_item(cx).into_iter().collect();
+                    items.append(&mut fuzzy_match(
+                        rest.trim(),
+                        Numbat::history_items(&numbat, cx),
+                        false,
+                    ));
+                    return items;
+                }
+                // Blend the launch history into the fuzzy score, so frequently used items float up
+                let frecency = cx.global::<Frecency>();
+                let now = Timestamp::now();
+                let mut items: Vec<(Item, f64)> = fuzzy_score(&query, items, false)
+                    .into_iter()
+                    .map(|(item, score)| {
+                        let rank = (score as f64 + 1.0) * frecency.boost(item.get_id(), now);
+                        (item, rank)
+                    })
+                    .collect();
+                items.sort_by(|a, b| b.1.total_cmp(&a.1));
+                let mut items: Vec<Item> = items.into_iter().map(|(item, _)| item).collect();
                 if items.is_empty() {
-                    if let Some(result) = numbat.read(cx).result.clone() {
-                        items.push(
-                            ItemBuilder::new(
-                                "Numbat",
-                                NumbatWrapper {
-                                    inner: numbat.clone(),
-                                },
-                            )
-                            .actions(vec![LAction::new(
-                                Img::default().icon(Icon::Copy),
-                                "Copy",
-                                None,
-                                {
-                                    move |this, cx: &mut App| {
-                                        cx.write_to_clipboard(ClipboardItem::new_string(
-                                            result.result.to_string(),
-                                        ));
-                                        this.toast.floating(
-                                            "Copied to clipboard",
-                                            Some(Icon::Clipboard),
-                                            window,
-                                            cx,
-                                        );
-                                        LWindow::close(cx);
-                                    }
-                                },
-                                false,
-                            )])
-                            .build(),
-                        );
-                    }
+                    items.extend(numbat_item(cx));
                 }
                 items
             })
             .build(
                 |_, _, _cx| {
                     {
-                        let application_entries = get_application_files();
+                        let application_entries = get_applications();
 
                         let mut apps = HashMap::<String, Item>::new();
 
-                        for entry in application_entries {
-                            // search for .icns in Contents/Resources
-                            let data = get_application_data(&entry);
-                            if data.is_none() {
-                                continue;
-                            }
-                            let data = data.unwrap();
-                            let app = ItemBuilder::new(
-                                data.id.clone(),
-                                ListItem::new(
-                                    Some(data.icon.clone()),
-                                    data.name.clone(),
-                                    None,
-                                    vec![Accessory::new(data.tag.clone(), None)],
-                                ),
-                            )
-                            .keywords(vec![data.name.clone()])
-                            .actions(vec![LAction::new(
+                        for (entry, data) in application_entries {
+                            #[cfg(target_os = "linux")]
+                            let desktop_actions: Vec<LAction> = data
+                                .actions
+                                .iter()
+                                .map(|action| {
+                                    LAction::new(
+                                        action.icon.clone().unwrap_or_else(|| {
+                                            Img::default().icon(Icon::ArrowUpRightFromSquare)
+                                        }),
+                                        action.name.clone(),
+                                        None,
+                                        {
+                                            let entry = entry.clone();
+                                            let id = action.id.clone();
+                                            move |this, cx| {
+                                                if let Err(err) =
+                                                    launch_application(&entry, Some(&id))
+                                                {
+                                                    error!("Failed to launch action: {}", err);
+                                                    this.toast.error(err.to_string(), cx);
+                                                    return;
+                                                }
+                                                LWindow::close(cx);
+                                            }
+                                        },
+                                        false,
+                                    )
+                                })
+                                .collect();
+                            #[cfg(not(target_os = "linux"))]
+                            let desktop_actions: Vec<LAction> = vec![];
+                            let open = LAction::new(
                                 Img::default().icon(Icon::ArrowUpRightFromSquare),
                                 format!("Open {}", data.tag.clone()),
                                 None,
@@ -135,12 +168,14 @@ impl StateViewBuilder for RootListBuilder {
                                     }
                                     #[cfg(target_os = "linux")]
                                     {
-                                        move |_, cx| {
+                                        let entry = entry.clone();
+                                        move |this, cx| {
+                                            if let Err(err) = launch_application(&entry, None) {
+                                                error!("Failed to launch {}: {}", data_id, err);
+                                                this.toast.error(err.to_string(), cx);
+                                                return;
+                                            }
                                             LWindow::close(cx);
-                                            let mut command =
-                                                std::process::Command::new("gtk-launch");
-                                            command.arg(data_id.clone());
-                                            let _ = command.spawn();
                                         }
                                     }
                                     #[cfg(target_os = "windows")]
@@ -196,7 +231,25 @@ impl StateViewBuilder for RootListBuilder {
                                     }
                                 },
                                 false,
-                            )])
+                            );
+                            let app = ItemBuilder::new(
+                                data.id.clone(),
+                                ListItem::new(
+                                    Some(data.icon.clone()),
+                                    data.name.clone(),
+                                    None,
+                                    vec![Accessory::new(data.tag.clone(), None)],
+                                ),
+                            )
+                            .keywords([vec![data.name.clone()], data.keywords.clone()].concat())
+                            .actions(
+                                [
+                                    vec![Frecency::track(data.id.clone(), open)],
+                                    desktop_actions,
+                                    vec![Frecency::clear_action(data.id.clone())],
+                                ]
+                                .concat(),
+                            )
                             .build();
                             apps.insert(data.id, app);
                         }
@@ -210,6 +263,56 @@ impl StateViewBuilder for RootListBuilder {
                 cx,
             );
 
+        // Typing an alias followed by a space jumps into its command, with the rest as its query
+        if let Some(query) = context.query.view.upgrade() {
+            let root_query = context.query.clone();
+            cx.subscribe(&query, move |_, event: &TextEvent, cx| {
+                let TextEvent::Input { text } = event else {
+                    return;
+                };
+                let Some((command, rest)) = CommandAliases::resolve(text, cx) else {
+                    return;
+                };
+                root_query.set_text("", cx);
+                (command.action)(&mut LActions::default(cx), cx);
+                let query = cx
+                    .global::<StateModel>()
+                    .inner
+                    .read(cx)
+                    .stack
+                    .last()
+                    .map(|item| item.query.downgrade());
+                if let Some(query) = query {
+                    query.set_text(rest, cx);
+                }
+            })
+            .detach();
+        }
+
+        cx.observe_global::<Frecency>({
+            let list = list.clone();
+            move |cx| {
+                list.update(cx, |this, cx| {
+                    this.filter(true, cx);
+                });
+            }
+        })
+        .detach();
+
+        // Script commands can be added or removed at runtime, so keep the command items in sync
+        cx.observe_global::<RootCommands>({
+            let list = list.clone();
+            move |cx| {
+                commands.update(cx, |this, cx| {
+                    *this = RootCommands::list(window, cx);
+                });
+                list.update(cx, |this, cx| {
+                    this.filter(true, cx);
+                });
+            }
+        })
+        .detach();
+
         let list_clone = list.clone();
         cx.spawn(async move |cx| {
             let (tx, rx) = std::sync::mpsc::channel();
@@ -241,7 +344,7 @@ impl StateViewBuilder for RootListBuilder {
 pub struct LoungyCommandBuilder;
 command!(LoungyCommandBuilder);
 impl RootCommandBuilder for LoungyCommandBuilder {
-    fn build(&self, _: &mut Window, _cx: &mut App) -> RootCommand {
+    fn build(&self, window: &mut Window, _cx: &mut App) -> RootCommand {
         RootCommand::new(
             "loungy",
             "Loungy",
@@ -249,8 +352,8 @@ impl RootCommandBuilder for LoungyCommandBuilder {
             Icon::Rocket,
             vec!["Settings"],
             None,
-            |actions, cx| {
-                actions.toast.error("Preferences not yet implemented", cx);
+            |_, cx| {
+                StateModel::update(|this, cx| this.push(PreferencesBuilder, window, cx), cx);
             },
         )
     }
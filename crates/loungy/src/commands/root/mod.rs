@@ -9,5 +9,7 @@
  *
  */
 
+pub mod frecency;
 pub mod list;
 pub mod numbat;
+pub mod preferences;
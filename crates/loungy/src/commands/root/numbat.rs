@@ -8,38 +8,350 @@
  *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
  *
  */
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::Duration,
+};
+
+use bonsaidb::{
+    core::schema::{Collection, SerializedCollection},
+    local::Database,
+};
 use gpui::{
-    AnyElement, App, AppContext, Context, Entity, FontWeight, IntoElement, ParentElement, Render,
-    Styled, Window, div, svg,
+    AnyElement, AnyView, App, AppContext, BorrowAppContext, ClipboardItem, Context, Entity,
+    FontWeight, Global, IntoElement, ParentElement, Render, Styled, Window, div, svg,
 };
+use jiff::Timestamp;
+use log::error;
+use notify::Watcher;
+use notify_debouncer_full::new_debouncer;
 use numbat::{
     markup::{Formatter, PlainTextFormatter},
-    module_importer::BuiltinModuleImporter,
+    module_importer::{BuiltinModuleImporter, ChainedImporter, FileSystemImporter},
     pretty_print::PrettyPrint,
+    resolver::CodeSource,
 };
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    components::{list::ItemComponent, shared::Icon},
+    archive::Archived,
+    command,
+    components::{
+        list::{Accessory, Item, ItemBuilder, ItemComponent, ListBuilder, ListItem},
+        shared::{Icon, Img},
+    },
+    db::LDb,
+    paths::paths,
     query::{TextEvent, TextInputWeak},
+    state::{CommandTrait, LAction, StateModel, StateViewBuilder, StateViewContext},
     theme::LTheme,
+    window::LWindow,
 };
 
-#[derive(Clone)]
-
+#[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct NumbatResult {
-    equation: String,
+    // What was typed, evaluated again to carry the result over into `ans`
+    #[serde(default)]
+    pub input: String,
+    pub equation: String,
     pub result: String,
-    unit: String,
-    type_id: String,
+    pub unit: String,
+    pub type_id: String,
 }
 
-#[derive(Clone)]
-pub struct Numbat {
-    pub result: Option<NumbatResult>,
+impl NumbatResult {
+    fn with_unit(&self) -> String {
+        format!("{} {}", self.result, self.unit).trim().to_string()
+    }
+}
+
+pub(crate) fn db() -> &'static Database {
+    static DB: OnceLock<Database> = OnceLock::new();
+    DB.get_or_init(LDb::init_collection::<NumbatHistory>)
+}
+
+// Evaluated results, keyed by their equation
+#[derive(Serialize, Deserialize, Collection, Debug, Clone)]
+#[collection(name = "numbat.history")]
+pub struct NumbatHistory {
+    #[natural_id]
+    id: String,
+    result: NumbatResult,
+    last: Timestamp,
+}
+
+impl Archived for NumbatHistory {}
+
+// How long a result has to stay on screen before it counts as evaluated, so half typed queries stay out of the history
+const RECORD_DEBOUNCE: Duration = Duration::from_secs(2);
+
+// Refetched once the cached rates are older than this, the ECB publishes them once a day
+const RATES_MAX_AGE: i64 = 6 * 60 * 60;
+
+// Last fetched ECB reference rates, so currency conversion keeps working offline
+#[derive(Serialize, Deserialize)]
+struct ExchangeRates {
+    fetched: Timestamp,
+    // Publication date of the rates, as found in the XML
+    date: Option<String>,
+    xml: String,
+}
+
+impl ExchangeRates {
+    fn path() -> PathBuf {
+        paths().cache.join("numbat").join("exchange-rates.json")
+    }
+    fn load() -> Option<Self> {
+        Self::load_from(&Self::path())
+    }
+    fn load_from(path: &Path) -> Option<Self> {
+        serde_json::from_slice(&std::fs::read(path).ok()?).ok()
+    }
+    fn save(&self) -> anyhow::Result<()> {
+        let path = Self::path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_vec(self)?)?;
+        Ok(())
+    }
+    fn new(xml: String) -> Self {
+        // <Cube time='2024-05-17'>
+        let date = xml.split_once("time=").and_then(|(_, rest)| {
+            let quote = rest.chars().next()?;
+            rest[1..].split(quote).next().map(|date| date.to_string())
+        });
+        Self {
+            fetched: Timestamp::now(),
+            date,
+            xml,
+        }
+    }
+    fn is_stale(&self) -> bool {
+        Timestamp::now().as_second() - self.fetched.as_second() > RATES_MAX_AGE
+    }
+}
+
+// Statements that define something, half typed ones shouldn't stick around in the preview
+const DEFINITIONS: [&str; 6] = ["let", "fn", "unit", "dimension", "struct", "use"];
+
+fn defines(text: &str) -> bool {
+    text.lines().any(|line| {
+        let line = line.trim_start();
+        line.starts_with('@')
+            || line
+                .split_whitespace()
+                .next()
+                .is_some_and(|word| DEFINITIONS.contains(&word))
+    })
+}
+
+// Lives as long as the app, so definitions from `init.nbt` and `ans` carry over between searches
+pub struct NumbatContext {
+    ctx: numbat::Context,
+    // Snapshot of `ctx` the queries are evaluated on, taken again once it might differ
+    preview: Option<numbat::Context>,
+    // Input of the last accepted result, carried over into a reloaded context
+    ans: Option<String>,
+    // Unit names by the type they measure, collected the first time something gets converted
+    units: Option<HashMap<String, Vec<String>>>,
+    // Date of the exchange rates in use, if any were loaded yet
+    pub rates_date: Option<String>,
+}
+
+impl Global for NumbatContext {}
+
+impl NumbatContext {
+    // The prelude and `init.nbt`, along with the error if the latter failed to load
+    fn context() -> (numbat::Context, Option<String>) {
+        // Modules from the config folder can be imported with `use`, next to the builtin ones
+        let mut importer = FileSystemImporter::default();
+        importer.add_path(Self::root());
+        let importer = ChainedImporter::new(
+            Box::new(importer),
+            Box::new(BuiltinModuleImporter::default()),
+        );
+        let mut ctx = numbat::Context::new(importer);
+        ctx.load_currency_module_on_demand(true);
+        let _ = ctx.interpret("use prelude", CodeSource::Text);
+
+        let path = Self::root().join("init.nbt");
+        let mut failed = None;
+        if let Ok(code) = std::fs::read_to_string(&path) {
+            if let Err(err) = ctx.interpret(&code, CodeSource::File(path.clone())) {
+                error!("Failed to load {}: {}", path.display(), err);
+                failed = Some(format!("Failed to load {}", path.display()));
+            }
+        }
+        (ctx, failed)
+    }
+    pub fn init(cx: &mut App) {
+        let rates = ExchangeRates::load();
+        if let Some(rates) = &rates {
+            numbat::Context::set_exchange_rates(&rates.xml);
+        }
+        let (ctx, failed) = Self::context();
+        if let Some(message) = failed {
+            cx.defer(move |cx| StateModel::toast_error(message, cx));
+        }
+        cx.set_global(Self {
+            ctx,
+            preview: None,
+            ans: None,
+            units: None,
+            rates_date: rates.as_ref().and_then(|rates| rates.date.clone()),
+        });
+        if rates.is_none_or(|rates| rates.is_stale()) {
+            Self::refresh_rates(cx);
+        }
+        Self::watch(cx);
+    }
+    // Starts over from `init.nbt` and the modules next to it whenever they change
+    fn reload(cx: &mut App) {
+        let (mut ctx, failed) = Self::context();
+        if let Some(message) = failed {
+            StateModel::toast_error(message, cx);
+        }
+        cx.update_global::<Self, _>(|this, _| {
+            if let Some(input) = &this.ans {
+                let _ = ctx.interpret(&format!("let ans = ({})", input), CodeSource::Text);
+            }
+            this.ctx = ctx;
+            this.preview = None;
+            this.units = None;
+        });
+    }
+    fn watch(cx: &mut App) {
+        cx.spawn(async move |cx| {
+            let (tx, rx) = std::sync::mpsc::channel();
+            let mut debouncer = match new_debouncer(Duration::from_secs(1), None, tx) {
+                Ok(debouncer) => debouncer,
+                Err(err) => {
+                    error!("Failed to watch numbat modules: {:?}", err);
+                    return;
+                }
+            };
+            let root = Self::root();
+            if !root.exists() {
+                let _ = std::fs::create_dir_all(&root);
+            }
+            let _ = debouncer
+                .watcher()
+                .watch(&root, notify::RecursiveMode::Recursive);
+
+            loop {
+                if let Ok(Ok(_)) = rx.try_recv() {
+                    let _ = cx.update(Self::reload);
+                }
+                cx.background_executor().timer(Duration::from_secs(1)).await;
+            }
+        })
+        .detach();
+    }
+    fn refresh_rates(cx: &mut App) {
+        cx.spawn(async move |cx| {
+            let xml = cx
+                .background_executor()
+                .spawn(async { numbat_exchange_rates::fetch_exchange_rates() })
+                .await;
+            // Offline, keep using the cached rates
+            let Some(xml) = xml else {
+                error!("Failed to fetch exchange rates");
+                return;
+            };
+            let rates = ExchangeRates::new(xml);
+            numbat::Context::set_exchange_rates(&rates.xml);
+            if let Err(err) = rates.save() {
+                error!("Failed to cache exchange rates: {}", err);
+            }
+            let _ = cx.update_global::<Self, _>(|this, _| {
+                this.rates_date = rates.date;
+            });
+        })
+        .detach();
+    }
+    // Shown next to money amounts, so it's clear how current a conversion is
+    pub fn rates_accessory(result: &NumbatResult, cx: &App) -> Option<Accessory> {
+        if result.type_id.ne("Money") {
+            return None;
+        }
+        let date = cx.global::<Self>().rates_date.clone()?;
+        Some(Accessory::new(
+            date,
+            Some(Img::default().icon(Icon::Calendar)),
+        ))
+    }
+    pub fn root() -> PathBuf {
+        paths().config.join("numbat")
+    }
+    fn preview(&mut self) -> &mut numbat::Context {
+        self.preview.get_or_insert_with(|| self.ctx.clone())
+    }
+    // Runs on the snapshot, results of half typed queries shouldn't leak into the session
+    fn evaluate(&mut self, text: &str) -> Option<NumbatResult> {
+        let result = interpret(self.preview(), text);
+        if defines(text) {
+            self.preview = None;
+        }
+        result
+    }
+    // Keeps the definitions of an accepted input and makes its result available as `ans`
+    pub fn commit(result: &NumbatResult, cx: &mut App) {
+        let input = if result.input.is_empty() {
+            result.equation.clone()
+        } else {
+            result.input.clone()
+        };
+        cx.update_global::<Self, _>(|this, _| {
+            if this.ctx.interpret(&input, CodeSource::Text).is_ok() {
+                let _ = this
+                    .ctx
+                    .interpret(&format!("let ans = ({})", input), CodeSource::Text);
+                this.preview = None;
+                this.ans = Some(input);
+            }
+        });
+    }
+    // Probes every unit on a fresh context, which is slow enough to belong on the background executor.
+    // Units from `init.nbt` and the modules next to it are there, ones defined in the session aren't
+    fn probe_units() -> HashMap<String, Vec<String>> {
+        let (mut ctx, _) = Self::context();
+        let names: Vec<String> = ctx
+            .unit_names()
+            .iter()
+            .filter_map(|names| names.first().cloned())
+            .collect();
+        let mut units: HashMap<String, Vec<String>> = HashMap::new();
+        for name in names {
+            if let Some(result) = interpret(&mut ctx, &name) {
+                units.entry(result.type_id).or_default().push(name);
+            }
+        }
+        units
+    }
+    // `None` until the units were probed
+    pub fn conversions(&mut self, result: &NumbatResult) -> Option<Vec<NumbatResult>> {
+        if result.type_id.is_empty() {
+            return Some(vec![]);
+        }
+        let units = self.units.as_ref()?.get(&result.type_id).cloned();
+        let ctx = self.preview();
+        Some(
+            units
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|unit| unit.ne(&result.unit))
+                .filter_map(|unit| interpret(ctx, &format!("({}) -> {}", result.with_unit(), unit)))
+                .collect(),
+        )
+    }
 }
 
 fn rephraser(s: &str) -> String {
-    let mut s = s.to_string();
+    // `=` only switches the root list to the history
+    let mut s = s.trim_start_matches('=').to_string();
     if s.contains(" and ") {
         s = s.replace(" and ", " + ");
     }
@@ -49,72 +361,290 @@ fn rephraser(s: &str) -> String {
     s
 }
 
+fn interpret(ctx: &mut numbat::Context, text: &str) -> Option<NumbatResult> {
+    let (statements, result) = ctx.interpret(text, CodeSource::Text).ok()?;
+    let formatter = PlainTextFormatter {};
+    let s: Vec<String> = statements
+        .iter()
+        .map(|s| formatter.format(&s.pretty_print(), false).to_string())
+        .collect();
+    let s = s.join(" ");
+    let result = &result.to_markup(statements.last(), ctx.dimension_registry(), true, true);
+    let mut value: Option<String> = None;
+    let mut type_id: Option<String> = None;
+    let mut unit: Option<String> = None;
+    for part in &result.0 {
+        match part.1 {
+            numbat::markup::FormatType::String => value = Some(part.2.to_string()),
+            numbat::markup::FormatType::Value => {
+                value = Some(part.2.to_string());
+            }
+            numbat::markup::FormatType::TypeIdentifier => {
+                type_id = Some(part.2.to_string());
+            }
+            numbat::markup::FormatType::Unit => {
+                unit = Some(part.2.to_string());
+            }
+            _ => {}
+        }
+    }
+    value.map(|value| NumbatResult {
+        input: text.to_string(),
+        result: value,
+        unit: unit.unwrap_or_default(),
+        type_id: type_id.unwrap_or_default(),
+        equation: s.replace('➞', "to"),
+    })
+}
+
+pub struct Numbat {
+    pub result: Option<NumbatResult>,
+    pub history: Vec<NumbatHistory>,
+}
+
 impl Numbat {
     pub fn init(query: &TextInputWeak, cx: &mut App) -> Entity<Numbat> {
-        let importer = BuiltinModuleImporter::default();
-        let mut ctx = numbat::Context::new(importer);
-        ctx.load_currency_module_on_demand(true);
-        numbat::Context::prefetch_exchange_rates();
-        let _ = ctx.interpret("use prelude", numbat::resolver::CodeSource::Text);
+        let mut history: Vec<NumbatHistory> = NumbatHistory::all(db())
+            .query()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|doc| doc.contents)
+            .collect();
+        history.sort_by(|a, b| b.last.cmp(&a.last));
 
         cx.new(move |cx| {
             if let Some(query) = query.view.upgrade() {
                 cx.subscribe(&query, move |subscriber: &mut Numbat, _, event, cx| {
                     if let TextEvent::Input { text } = event {
-                        let text = rephraser(&text);
-                        let result = ctx.interpret(&text, numbat::resolver::CodeSource::Text);
-                        let formatter = PlainTextFormatter {};
-                        subscriber.result = match result {
-                            Ok((statements, result)) => {
-                                let s: Vec<String> = statements
-                                    .iter()
-                                    .map(|s| formatter.format(&s.pretty_print(), false).to_string())
-                                    .collect();
-                                let s = s.join(" ");
-                                let result = &result.to_markup(
-                                    statements.last(),
-                                    ctx.dimension_registry(),
-                                    true,
-                                    true,
-                                );
-                                let mut value: Option<String> = None;
-                                let mut type_id: Option<String> = None;
-                                let mut unit: Option<String> = None;
-                                for part in &result.0 {
-                                    match part.1 {
-                                        numbat::markup::FormatType::String => {
-                                            value = Some(part.2.to_string())
-                                        }
-                                        numbat::markup::FormatType::Value => {
-                                            value = Some(part.2.to_string());
-                                        }
-                                        numbat::markup::FormatType::TypeIdentifier => {
-                                            type_id = Some(part.2.to_string());
-                                        }
-                                        numbat::markup::FormatType::Unit => {
-                                            unit = Some(part.2.to_string());
-                                        }
-                                        _ => {}
+                        subscriber.result = cx.update_global::<NumbatContext, _>(|this, _| {
+                            this.evaluate(&rephraser(text))
+                        });
+                        // Plain numbers just echo themselves, there is nothing worth keeping
+                        if let Some(result) = subscriber
+                            .result
+                            .clone()
+                            .filter(|result| result.equation.ne(&result.with_unit()))
+                        {
+                            cx.spawn(async move |this, cx| {
+                                cx.background_executor().timer(RECORD_DEBOUNCE).await;
+                                let _ = this.update(cx, |this, _| {
+                                    // Superseded by another keystroke while waiting
+                                    if this
+                                        .result
+                                        .as_ref()
+                                        .is_some_and(|current| current.input.eq(&result.input))
+                                    {
+                                        this.record(&result);
                                     }
-                                }
-                                value.map(|value| NumbatResult {
-                                    result: value,
-                                    unit: unit.unwrap_or_default(),
-                                    type_id: type_id.unwrap_or_default(),
-                                    equation: s.replace('➞', "to"),
-                                })
-                            }
-                            Err(_e) => None,
-                        };
+                                });
+                            })
+                            .detach();
+                        }
                         cx.notify();
                     }
                 })
                 .detach();
             }
 
-            Numbat { result: None }
+            Numbat {
+                result: None,
+                history,
+            }
         })
     }
+    pub fn record(&mut self, result: &NumbatResult) {
+        let history = NumbatHistory {
+            id: result.equation.clone(),
+            result: result.clone(),
+            last: Timestamp::now(),
+        };
+        if let Err(err) = history.clone().overwrite_into(&history.id, db()) {
+            error!("Failed to save numbat history: {:?}", err);
+        }
+        self.history.retain(|h| h.id.ne(&history.id));
+        self.history.insert(0, history);
+    }
+    pub fn forget(&mut self, equation: &str) {
+        if let Ok(Some(history)) = NumbatHistory::get(&equation.to_string(), db()) {
+            if let Err(err) = history.delete(db()) {
+                error!("Failed to delete numbat history: {:?}", err);
+            }
+        }
+        self.history.retain(|h| h.id.ne(equation));
+    }
+    fn copy_action(
+        label: &str,
+        icon: Icon,
+        value: String,
+        result: &NumbatResult,
+        numbat: &Entity<Numbat>,
+    ) -> LAction {
+        let result = result.clone();
+        let numbat = numbat.clone();
+        LAction::new(
+            Img::default().icon(icon),
+            label,
+            None,
+            move |_, cx| {
+                cx.write_to_clipboard(ClipboardItem::new_string(value.clone()));
+                numbat.update(cx, |this, _| this.record(&result));
+                NumbatContext::commit(&result, cx);
+                LWindow::close(cx);
+            },
+            false,
+        )
+    }
+    pub fn actions(result: &NumbatResult, numbat: &Entity<Numbat>) -> Vec<LAction> {
+        let mut actions = vec![
+            Self::copy_action(
+                "Copy Value",
+                Icon::Copy,
+                result.result.clone(),
+                result,
+                numbat,
+            ),
+            Self::copy_action(
+                "Copy Value with Unit",
+                Icon::ClipboardCopy,
+                result.with_unit(),
+                result,
+                numbat,
+            ),
+            Self::copy_action(
+                "Copy Equation",
+                Icon::Equal,
+                format!("{} = {}", result.equation, result.with_unit()),
+                result,
+                numbat,
+            ),
+        ];
+        if !result.type_id.is_empty() {
+            let builder = NumbatConvertBuilder {
+                numbat: numbat.clone(),
+                result: result.clone(),
+            };
+            actions.push(LAction::new(
+                Img::default().icon(Icon::ArrowRightLeft),
+                "Convert to…",
+                None,
+                move |_, cx| {
+                    let builder = builder.clone();
+                    let Some(handle) = cx.windows().first().copied() else {
+                        return;
+                    };
+                    let _ = cx.update_window(handle, |_, window, cx| {
+                        StateModel::update(|this, cx| this.push(builder, window, cx), cx);
+                    });
+                },
+                false,
+            ));
+        }
+        actions
+    }
+    // Items for a query starting with `=`
+    pub fn history_items(numbat: &Entity<Numbat>, cx: &App) -> Vec<Item> {
+        numbat
+            .read(cx)
+            .history
+            .iter()
+            .map(|history| {
+                let result = &history.result;
+                let mut actions = Self::actions(result, numbat);
+                actions.push({
+                    let numbat = numbat.clone();
+                    let id = history.id.clone();
+                    LAction::new(
+                        Img::default().icon(Icon::Trash),
+                        "Remove from History",
+                        None,
+                        move |actions, cx| {
+                            numbat.update(cx, |this, _| this.forget(&id));
+                            actions.toast.success("Removed from history", cx);
+                        },
+                        false,
+                    )
+                });
+                let mut accessories = vec![Accessory::new(result.with_unit(), None)];
+                accessories.extend(NumbatContext::rates_accessory(result, cx));
+                ItemBuilder::new(
+                    ("numbat", history.id.clone()),
+                    ListItem::new(
+                        Some(Img::default().icon(Icon::Calculator)),
+                        result.equation.clone(),
+                        None,
+                        accessories,
+                    ),
+                )
+                .keywords(vec![result.equation.clone(), result.with_unit()])
+                .actions(actions)
+                .build()
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone)]
+pub struct NumbatConvertBuilder {
+    numbat: Entity<Numbat>,
+    result: NumbatResult,
+}
+command!(NumbatConvertBuilder);
+impl StateViewBuilder for NumbatConvertBuilder {
+    fn build(&self, context: &mut StateViewContext, window: &mut Window, cx: &mut App) -> AnyView {
+        context
+            .query
+            .set_placeholder(format!("Convert {} to...", self.result.with_unit()), cx);
+        let numbat = self.numbat.clone();
+        let result = self.result.clone();
+        ListBuilder::new()
+            .build(
+                move |_, _, cx| {
+                    let Some(conversions) =
+                        cx.update_global::<NumbatContext, _>(|this, _| this.conversions(&result))
+                    else {
+                        // The list fills in once the units are known
+                        cx.spawn(async move |this, cx| {
+                            let units = cx
+                                .background_executor()
+                                .spawn(async { NumbatContext::probe_units() })
+                                .await;
+                            let _ = cx.update_global::<NumbatContext, _>(|this, _| {
+                                this.units = Some(units);
+                            });
+                            let _ = this.update(cx, |this, cx| this.update(true, cx));
+                        })
+                        .detach();
+                        return Ok(None);
+                    };
+                    Ok(Some(
+                        conversions
+                            .iter()
+                            .map(|converted| {
+                                let mut accessories =
+                                    vec![Accessory::new(converted.with_unit(), None)];
+                                accessories.extend(NumbatContext::rates_accessory(converted, cx));
+                                ItemBuilder::new(
+                                    converted.equation.clone(),
+                                    ListItem::new(
+                                        Some(Img::default().icon(Icon::Ruler)),
+                                        converted.unit.clone(),
+                                        None,
+                                        accessories,
+                                    ),
+                                )
+                                .keywords(vec![converted.unit.clone()])
+                                .actions(Numbat::actions(converted, &numbat))
+                                .build()
+                            })
+                            .collect(),
+                    ))
+                },
+                context,
+                window,
+                cx,
+            )
+            .into()
+    }
 }
 
 #[derive(Clone)]
@@ -135,6 +665,7 @@ impl Render for Numbat {
             return div();
         }
         let result = self.result.as_ref().unwrap().clone();
+        let rates = NumbatContext::rates_accessory(&result, cx);
         let len = result
             .equation
             .len()
@@ -184,5 +715,71 @@ impl Render for Numbat {
                         .text_color(theme.surface0),
                 ),
         )
+        .children(rates.map(|rates| {
+            div()
+                .absolute()
+                .bottom_1()
+                .right_2()
+                .text_xs()
+                .font_weight(FontWeight::NORMAL)
+                .child(rates)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XML: &str = "<gesmes:Envelope><Cube><Cube time='2024-05-17'><Cube currency='USD' rate='1.0866'/></Cube></Cube></gesmes:Envelope>";
+
+    // A cached `exchange-rates.json` under the temp dir, fetched the given number of hours ago
+    fn fixture(name: &str, hours: i64) -> PathBuf {
+        let fetched =
+            Timestamp::from_second(Timestamp::now().as_second() - hours * 60 * 60).unwrap();
+        let path =
+            std::env::temp_dir().join(format!("loungy-rates-{}-{}.json", std::process::id(), name));
+        std::fs::write(
+            &path,
+            format!(
+                r#"{{"fetched":"{}","date":"2024-05-17","xml":"{}"}}"#,
+                fetched, XML
+            ),
+        )
+        .unwrap();
+        path
+    }
+
+    #[test]
+    fn date_from_xml() {
+        assert_eq!(
+            ExchangeRates::new(XML.into()).date.as_deref(),
+            Some("2024-05-17")
+        );
+        let xml = XML.replace('\'', "\"");
+        assert_eq!(ExchangeRates::new(xml).date.as_deref(), Some("2024-05-17"));
+        assert_eq!(ExchangeRates::new("<Cube/>".into()).date, None);
+    }
+
+    #[test]
+    fn load_cached_rates() {
+        let rates = ExchangeRates::load_from(&fixture("load", 1)).unwrap();
+        assert_eq!(rates.date.as_deref(), Some("2024-05-17"));
+        assert_eq!(rates.xml, XML);
+        assert!(ExchangeRates::load_from(Path::new("/nonexistent/exchange-rates.json")).is_none());
+    }
+
+    #[test]
+    fn stale_after_six_hours() {
+        assert!(
+            !ExchangeRates::load_from(&fixture("fresh", 5))
+                .unwrap()
+                .is_stale()
+        );
+        assert!(
+            ExchangeRates::load_from(&fixture("stale", 7))
+                .unwrap()
+                .is_stale()
+        );
     }
 }
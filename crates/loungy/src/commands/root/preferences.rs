@@ -0,0 +1,301 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+use gpui::{AnyView, App, BorrowAppContext, Window};
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    command,
+    commands::RootCommands,
+    components::form::{Form, Input, InputKind},
+    config::config,
+    db::db,
+    hotkey::{HotkeyManager, TOGGLE_ID},
+    platform::set_launch_at_login,
+    state::{CommandTrait, Shortcut, StateViewBuilder, StateViewContext},
+    theme::{LTheme, ThemeSettings},
+};
+
+use super::list::LoungyCommandBuilder;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Preferences {
+    pub clipboard_retention_days: u64,
+    // 0 means no limit
+    pub clipboard_max_entries: u64,
+    pub clipboard_max_image_mb: u64,
+    pub disabled_commands: Vec<String>,
+    pub launch_at_login: bool,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            clipboard_retention_days: 7,
+            clipboard_max_entries: 0,
+            clipboard_max_image_mb: 0,
+            disabled_commands: vec![],
+            launch_at_login: false,
+        }
+    }
+}
+
+impl Preferences {
+    pub fn get() -> Self {
+        db().get::<Preferences>("preferences").unwrap_or_default()
+    }
+    pub fn save(&self) -> anyhow::Result<()> {
+        db().set::<Preferences>("preferences", self)?;
+        Ok(())
+    }
+    pub fn is_enabled(id: &str) -> bool {
+        if let Some(enabled) = config().commands.get(id) {
+            return *enabled;
+        }
+        !Self::get().disabled_commands.iter().any(|d| d.eq(id))
+    }
+}
+
+fn checkbox(id: impl ToString, label: impl ToString, value: bool, cx: &mut App) -> Input {
+    Input::new(id, label, InputKind::Checkbox { value }, cx)
+}
+
+#[derive(Clone)]
+pub struct PreferencesBuilder;
+command!(PreferencesBuilder);
+impl StateViewBuilder for PreferencesBuilder {
+    fn build(&self, context: &mut StateViewContext, _: &mut Window, cx: &mut App) -> AnyView {
+        let preferences = Preferences::get();
+        let themes = db().get::<ThemeSettings>("theme").unwrap_or_default();
+
+        // The preferences themselves can't be disabled, otherwise there would be no way back
+        let own_id = LoungyCommandBuilder.command();
+        // Kept with their initial state, so only toggled commands end up saved
+        let mut commands: Vec<(String, String, bool)> = cx
+            .global::<RootCommands>()
+            .commands
+            .values()
+            .filter(|command| command.id.ne(&own_id))
+            .map(|command| {
+                (
+                    command.id.clone(),
+                    command.title.clone(),
+                    Preferences::is_enabled(&command.id),
+                )
+            })
+            .collect();
+        let theme_options: Vec<(String, String)> = LTheme::list()
+            .into_iter()
+            .map(|theme| (theme.name.clone(), theme.name))
+            .collect();
+        commands.sort_by(|a, b| a.1.cmp(&b.1));
+        let initial = preferences.clone();
+        let (light, dark) = (themes.light.clone(), themes.dark.clone());
+
+        let mut inputs = vec![
+            Input::new(
+                "light_theme",
+                "Light Theme",
+                InputKind::Dropdown {
+                    value: themes.light,
+                    options: theme_options.clone(),
+                },
+                cx,
+            ),
+            Input::new(
+                "dark_theme",
+                "Dark Theme",
+                InputKind::Dropdown {
+                    value: themes.dark,
+                    options: theme_options,
+                },
+                cx,
+            ),
+            #[cfg(feature = "clipboard")]
+            Input::new(
+                "clipboard_retention_days",
+                "Clipboard Retention (days, 0 to keep forever)",
+                InputKind::TextField {
+                    placeholder: "7".to_string(),
+                    value: preferences.clipboard_retention_days.to_string(),
+                    validate: Some(|v| {
+                        v.parse::<u64>()
+                            .is_err()
+                            .then_some("Must be a number of days")
+                    }),
+                    password: false,
+                },
+                cx,
+            ),
+            #[cfg(feature = "clipboard")]
+            Input::new(
+                "clipboard_max_entries",
+                "Clipboard Max Entries (0 for no limit)",
+                InputKind::TextField {
+                    placeholder: "0".to_string(),
+                    value: preferences.clipboard_max_entries.to_string(),
+                    validate: Some(|v| {
+                        v.parse::<u64>()
+                            .is_err()
+                            .then_some("Must be a number of entries")
+                    }),
+                    password: false,
+                },
+                cx,
+            ),
+            #[cfg(feature = "clipboard")]
+            Input::new(
+                "clipboard_max_image_mb",
+                "Clipboard Max Image Size (MB, 0 for no limit)",
+                InputKind::TextField {
+                    placeholder: "0".to_string(),
+                    value: preferences.clipboard_max_image_mb.to_string(),
+                    validate: Some(|v| {
+                        v.parse::<u64>()
+                            .is_err()
+                            .then_some("Must be a number of megabytes")
+                    }),
+                    password: false,
+                },
+                cx,
+            ),
+            checkbox(
+                "launch_at_login",
+                "Launch at Login",
+                preferences.launch_at_login,
+                cx,
+            ),
+        ];
+        // A toggle from the config file can only be changed there
+        if !HotkeyManager::is_configured(TOGGLE_ID) {
+            let hotkey = Some(Shortcut::from(&HotkeyManager::get_toggle()));
+            inputs.insert(
+                0,
+                Input::new(
+                    "hotkey",
+                    "Toggle Hotkey",
+                    InputKind::Shortcut {
+                        tmp: hotkey.clone(),
+                        value: hotkey,
+                    },
+                    cx,
+                ),
+            );
+        }
+        for (id, title, enabled) in &commands {
+            inputs.push(checkbox(
+                format!("command:{}", id),
+                format!("Enable {}", title),
+                *enabled,
+                cx,
+            ));
+        }
+
+        Form::new(
+            inputs,
+            move |values, actions, cx| {
+                let mut preferences = Preferences::get();
+
+                if let Some(shortcut) = values
+                    .get("hotkey")
+                    .and_then(|input| input.value::<Option<Shortcut>>())
+                    .filter(|shortcut| !HotkeyManager::is_toggle(&shortcut.get()))
+                {
+                    if let Err(err) = HotkeyManager::set_toggle(shortcut.get(), cx) {
+                        error!("Failed to set toggle hotkey: {}", err);
+                        actions.toast.error(err.to_string(), cx);
+                        return;
+                    }
+                }
+
+                let themes = ThemeSettings {
+                    light: values["light_theme"].value::<String>(),
+                    dark: values["dark_theme"].value::<String>(),
+                };
+                if themes.light.ne(&light) || themes.dark.ne(&dark) {
+                    if db().set::<ThemeSettings>("theme", &themes).is_err() {
+                        actions.toast.error("Failed to save themes", cx);
+                        return;
+                    }
+                    let theme = LTheme::mode(cx.window_appearance());
+                    cx.update_global::<LTheme, _>(|this, _| {
+                        *this = theme;
+                    });
+                }
+
+                #[cfg(feature = "clipboard")]
+                {
+                    let number = |id: &str, initial: u64, current: u64| {
+                        values[id]
+                            .value::<String>()
+                            .parse()
+                            .ok()
+                            .filter(|value| *value != initial)
+                            .unwrap_or(current)
+                    };
+                    preferences.clipboard_retention_days = number(
+                        "clipboard_retention_days",
+                        initial.clipboard_retention_days,
+                        preferences.clipboard_retention_days,
+                    );
+                    preferences.clipboard_max_entries = number(
+                        "clipboard_max_entries",
+                        initial.clipboard_max_entries,
+                        preferences.clipboard_max_entries,
+                    );
+                    preferences.clipboard_max_image_mb = number(
+                        "clipboard_max_image_mb",
+                        initial.clipboard_max_image_mb,
+                        preferences.clipboard_max_image_mb,
+                    );
+                }
+
+                let launch_at_login = values["launch_at_login"].value::<bool>();
+                if launch_at_login != initial.launch_at_login {
+                    if let Err(err) = set_launch_at_login(launch_at_login) {
+                        error!("Failed to change launch at login: {}", err);
+                        actions.toast.error("Failed to change launch at login", cx);
+                        return;
+                    }
+                    preferences.launch_at_login = launch_at_login;
+                }
+
+                // Untouched commands keep their state, so overrides from the config file aren't persisted
+                for (id, _, enabled) in &commands {
+                    let value = values[&format!("command:{}", id)].value::<bool>();
+                    if value == *enabled {
+                        continue;
+                    }
+                    preferences.disabled_commands.retain(|d| d.ne(id));
+                    if !value {
+                        preferences.disabled_commands.push(id.clone());
+                    }
+                }
+
+                if let Err(err) = preferences.save() {
+                    error!("Failed to save preferences: {}", err);
+                    actions.toast.error("Failed to save preferences", cx);
+                    return;
+                }
+                // Refresh everything depending on the enabled commands
+                cx.update_global::<RootCommands, _>(|_, _| {});
+                HotkeyManager::update(cx);
+                cx.refresh();
+                actions.toast.success("Preferences saved", cx);
+            },
+            context,
+            cx,
+        )
+        .into()
+    }
+}
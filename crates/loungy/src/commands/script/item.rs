@@ -0,0 +1,160 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+use gpui::ClipboardItem;
+use log::error;
+use serde::Deserialize;
+
+use crate::{
+    commands::paste,
+    components::{
+        list::{Accessory, Item, ItemBuilder, ListItem},
+        shared::{Icon, Img},
+    },
+    state::{LAction, Shortcut},
+    window::LWindow,
+};
+
+use super::manifest::Script;
+
+fn default_icon() -> Icon {
+    Icon::ArrowRight
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum ScriptAccessory {
+    Text(String),
+    Tag { text: String, icon: Option<Icon> },
+}
+
+impl From<ScriptAccessory> for Accessory {
+    fn from(accessory: ScriptAccessory) -> Self {
+        match accessory {
+            ScriptAccessory::Text(text) => Accessory::new(text, None),
+            ScriptAccessory::Tag { text, icon } => {
+                Accessory::new(text, icon.map(|icon| Img::default().icon(icon)))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ScriptActionKind {
+    // Runs a command, relative paths are resolved against the command folder
+    Exec { command: Vec<String> },
+    Copy { text: String },
+    Paste { text: String },
+    Open { url: String },
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ScriptAction {
+    pub title: String,
+    #[serde(default = "default_icon")]
+    pub icon: Icon,
+    // Keystroke such as "cmd-c", only applies to secondary actions
+    pub shortcut: Option<String>,
+    #[serde(flatten)]
+    pub kind: ScriptActionKind,
+}
+
+impl ScriptAction {
+    fn shortcut(&self) -> Option<Shortcut> {
+        let shortcut = self.shortcut.as_ref()?;
+        let mut tokens: Vec<&str> = shortcut.split('-').collect();
+        let key = tokens.pop()?;
+        Some(
+            tokens
+                .into_iter()
+                .fold(Shortcut::new(key), |shortcut, token| match token {
+                    "cmd" => shortcut.cmd(),
+                    "ctrl" => shortcut.ctrl(),
+                    "alt" => shortcut.alt(),
+                    "shift" => shortcut.shift(),
+                    _ => shortcut,
+                }),
+        )
+    }
+    fn into_action(self, script: &Script) -> LAction {
+        let shortcut = self.shortcut();
+        let script = script.clone();
+        LAction::new(
+            Img::default().icon(self.icon.clone()),
+            self.title.clone(),
+            shortcut,
+            move |actions, cx| match self.kind.clone() {
+                ScriptActionKind::Exec { command } => {
+                    if let Err(err) = script.spawn(&command) {
+                        error!("Failed to run script action: {:?}", err);
+                        actions.toast.error(err, cx);
+                    } else {
+                        LWindow::close(cx);
+                    }
+                }
+                ScriptActionKind::Copy { text } => {
+                    cx.write_to_clipboard(ClipboardItem::new_string(text));
+                    actions.toast.success("Copied to clipboard", cx);
+                }
+                ScriptActionKind::Paste { text } => {
+                    paste(&text, cx);
+                }
+                ScriptActionKind::Open { url } => {
+                    cx.open_url(&url);
+                    LWindow::close(cx);
+                }
+            },
+            false,
+        )
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ScriptItem {
+    pub id: Option<String>,
+    pub title: String,
+    pub subtitle: Option<String>,
+    pub icon: Option<Icon>,
+    #[serde(default)]
+    pub accessories: Vec<ScriptAccessory>,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    #[serde(default)]
+    pub actions: Vec<ScriptAction>,
+}
+
+impl ScriptItem {
+    pub fn parse(stdout: &str) -> anyhow::Result<Vec<Self>> {
+        Ok(serde_json::from_str(stdout)?)
+    }
+    pub fn into_item(self, script: &Script) -> Item {
+        let mut keywords = vec![self.title.clone()];
+        keywords.extend(self.keywords);
+        ItemBuilder::new(
+            self.id.unwrap_or_else(|| self.title.clone()),
+            ListItem::new(
+                self.icon.map(|icon| Img::default().icon(icon)),
+                self.title,
+                self.subtitle,
+                self.accessories.into_iter().map(Accessory::from).collect(),
+            ),
+        )
+        .keywords(keywords)
+        .actions(
+            self.actions
+                .into_iter()
+                .map(|action| action.into_action(script))
+                .collect(),
+        )
+        .build()
+    }
+}
@@ -0,0 +1,248 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+use gpui::{AnyView, App, BorrowAppContext, ClipboardItem, Window};
+use log::error;
+use notify::Watcher;
+use notify_debouncer_full::new_debouncer;
+
+use crate::{
+    command,
+    commands::{RootCommand, RootCommands},
+    components::{
+        list::{Item, ItemBuilder, ListBuilder, ListItem},
+        shared::{Icon, Img},
+    },
+    hotkey::HotkeyManager,
+    state::{CommandTrait, LAction, StateModel, StateViewBuilder, StateViewContext},
+};
+
+use super::{
+    item::ScriptItem,
+    manifest::{Script, ScriptOutput},
+};
+
+// Pause after the last keystroke before a live script is run again
+const LIVE_DEBOUNCE: Duration = Duration::from_millis(150);
+
+// Turns the stdout of the script into list items, based on the manifest output kind
+fn script_items(script: &Script, stdout: &str) -> anyhow::Result<Vec<Item>> {
+    if script.manifest.output == ScriptOutput::Json {
+        return Ok(ScriptItem::parse(stdout)?
+            .into_iter()
+            .map(|item| item.into_item(script))
+            .collect());
+    }
+    Ok(stdout
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .enumerate()
+        .map(|(i, line)| {
+            ItemBuilder::new((i, line), ListItem::new(None, line, None, vec![]))
+                .keywords(vec![line])
+                .actions(vec![LAction::new(
+                    Img::default().icon(Icon::Clipboard),
+                    "Copy",
+                    None,
+                    {
+                        let line = line.to_string();
+                        move |this, cx| {
+                            cx.write_to_clipboard(ClipboardItem::new_string(line.clone()));
+                            this.toast.success("Copied to clipboard", cx);
+                        }
+                    },
+                    false,
+                )])
+                .build()
+        })
+        .collect())
+}
+
+#[derive(Clone)]
+pub struct ScriptListBuilder {
+    script: Script,
+}
+command!(ScriptListBuilder);
+impl StateViewBuilder for ScriptListBuilder {
+    fn build(&self, context: &mut StateViewContext, window: &mut Window, cx: &mut App) -> AnyView {
+        context.query.set_placeholder(
+            format!("Search {}...", self.script.manifest.title.to_lowercase()),
+            cx,
+        );
+        let script = self.script.clone();
+        let mut builder = ListBuilder::new();
+        if script.manifest.live {
+            // Live scripts do their own filtering, so they get re-invoked with every query change.
+            // Until the run for the current query is done, the previous results stay in place
+            let script = script.clone();
+            let results: Rc<RefCell<(Option<String>, Vec<Item>)>> = Rc::default();
+            builder = builder.filter(move |this, cx| {
+                let query = this.query.get_text(cx);
+                let (last, items) = results.borrow().clone();
+                if last.as_ref() == Some(&query) {
+                    return items;
+                }
+                let script = script.clone();
+                let results = results.clone();
+                cx.spawn(async move |this, cx| {
+                    cx.background_executor().timer(LIVE_DEBOUNCE).await;
+                    // Superseded by another keystroke while waiting
+                    let current = this.update(cx, |this, cx| this.query.get_text(cx));
+                    if current.ok().as_ref() != Some(&query) {
+                        return;
+                    }
+                    let stdout = cx
+                        .background_executor()
+                        .spawn({
+                            let script = script.clone();
+                            let query = query.clone();
+                            async move { script.run(&[query]) }
+                        })
+                        .await;
+                    let _ = this.update(cx, |this, cx| {
+                        // Results for anything but the current query are dropped
+                        if this.query.get_text(cx) != query {
+                            return;
+                        }
+                        let items = stdout
+                            .and_then(|stdout| script_items(&script, &stdout))
+                            .unwrap_or_else(|err| {
+                                error!("Script command failed: {:?}", err);
+                                vec![]
+                            });
+                        *results.borrow_mut() = (Some(query), items);
+                        this.filter(true, cx);
+                    });
+                })
+                .detach();
+                items
+            });
+        }
+        builder
+            .build(
+                move |_, _, _| {
+                    if script.manifest.live {
+                        return Ok(Some(vec![]));
+                    }
+                    Ok(Some(script_items(&script, &script.run(&[])?)?))
+                },
+                context,
+                window,
+                cx,
+            )
+            .into()
+    }
+}
+
+fn script_command(script: Script, window: &mut Window) -> RootCommand {
+    let manifest = script.manifest.clone();
+    RootCommand::new(
+        script.command_id(),
+        manifest.title.clone(),
+        if manifest.subtitle.is_empty() {
+            "Script".to_string()
+        } else {
+            manifest.subtitle.clone()
+        },
+        manifest.icon.clone(),
+        manifest.keywords.clone(),
+        None,
+        move |actions, cx| {
+            let script = script.clone();
+            match script.manifest.output {
+                ScriptOutput::Toast => {
+                    let mut toast = actions.toast.clone();
+                    toast.loading(format!("Running {}...", script.manifest.title), cx);
+                    cx.spawn(async move |cx| {
+                        let result = cx
+                            .background_executor()
+                            .spawn(async move { script.run(&[]) })
+                            .await;
+                        match result {
+                            Ok(stdout) => {
+                                let message = stdout
+                                    .lines()
+                                    .map(|line| line.trim())
+                                    .find(|line| !line.is_empty())
+                                    .unwrap_or("Done")
+                                    .to_string();
+                                toast.success(message, cx);
+                            }
+                            Err(err) => {
+                                error!("Script command failed: {:?}", err);
+                                toast.error(err, cx);
+                            }
+                        }
+                    })
+                    .detach();
+                }
+                ScriptOutput::List | ScriptOutput::Json => {
+                    StateModel::update(
+                        |this, cx| this.push(ScriptListBuilder { script }, window, cx),
+                        cx,
+                    );
+                }
+            }
+        },
+    )
+}
+
+pub fn script_commands(window: &mut Window) -> Vec<RootCommand> {
+    Script::load_all()
+        .into_iter()
+        .map(|script| script_command(script, window))
+        .collect()
+}
+
+// Re-registers the script commands whenever something in the commands folder changes
+pub fn watch(window: &mut Window, cx: &mut App) {
+    cx.spawn(async move |cx| {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut debouncer = match new_debouncer(Duration::from_secs(1), None, tx) {
+            Ok(debouncer) => debouncer,
+            Err(err) => {
+                error!("Failed to watch script commands: {:?}", err);
+                return;
+            }
+        };
+        let root = Script::root();
+        if !root.exists() {
+            let _ = std::fs::create_dir_all(&root);
+        }
+        let _ = debouncer
+            .watcher()
+            .watch(&root, notify::RecursiveMode::Recursive);
+
+        loop {
+            if rx.try_recv().is_ok() {
+                let _ = cx.update(|cx| {
+                    let scripts = script_commands(window);
+                    cx.update_global::<RootCommands, _>(|this, _| {
+                        this.commands.retain(|id, _| !Script::is_command_id(id));
+                        for command in scripts {
+                            if this.commands.contains_key(&command.id) {
+                                error!("Script command id already in use: {}", command.id);
+                                continue;
+                            }
+                            this.commands.insert(command.id.clone(), command);
+                        }
+                    });
+                    HotkeyManager::update(cx);
+                });
+            }
+            cx.background_executor().timer(Duration::from_secs(1)).await;
+        }
+    })
+    .detach();
+}
@@ -0,0 +1,150 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+use anyhow::anyhow;
+use log::error;
+use serde::Deserialize;
+
+use crate::{components::shared::Icon, paths::paths};
+
+pub static MANIFEST: &str = "manifest.toml";
+
+fn default_icon() -> Icon {
+    Icon::Terminal
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScriptOutput {
+    #[default]
+    Toast,
+    List,
+    // stdout is a JSON array of list items, see `ScriptItem`
+    Json,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ScriptManifest {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub subtitle: String,
+    #[serde(default = "default_icon")]
+    pub icon: Icon,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    // Path to the executable, relative to the command folder
+    pub exec: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub output: ScriptOutput,
+    // Re-runs the script with the query as last argument whenever it changes
+    #[serde(default)]
+    pub live: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct Script {
+    pub manifest: ScriptManifest,
+    pub dir: PathBuf,
+}
+
+impl Script {
+    pub fn root() -> PathBuf {
+        paths().config.join("commands")
+    }
+    pub fn load(dir: PathBuf) -> anyhow::Result<Self> {
+        let manifest = fs::read_to_string(dir.join(MANIFEST))?;
+        let manifest: ScriptManifest = toml::from_str(&manifest)?;
+        if manifest.id.is_empty() || manifest.id.contains("::") {
+            return Err(anyhow!("Invalid command id: {:?}", manifest.id));
+        }
+        Ok(Self { manifest, dir })
+    }
+    pub fn load_all() -> Vec<Self> {
+        let Ok(entries) = fs::read_dir(Self::root()) else {
+            return vec![];
+        };
+        let mut scripts = HashMap::<String, Self>::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.join(MANIFEST).is_file() {
+                continue;
+            }
+            match Self::load(path.clone()) {
+                Ok(script) => {
+                    if scripts.contains_key(&script.manifest.id) {
+                        error!("Duplicate script command id: {}", script.manifest.id);
+                        continue;
+                    }
+                    scripts.insert(script.manifest.id.clone(), script);
+                }
+                Err(err) => error!("Failed to load script command {:?}: {}", path, err),
+            }
+        }
+        scripts.into_values().collect()
+    }
+    // Root command id, shaped like the builtin module paths so the IPC `command` action can address it by its manifest id
+    pub fn command_id(&self) -> String {
+        format!("loungy::commands::{}::script", self.manifest.id)
+    }
+    pub fn is_command_id(id: &str) -> bool {
+        id.starts_with("loungy::commands::") && id.ends_with("::script")
+    }
+    pub fn exec(&self) -> PathBuf {
+        self.dir.join(&self.manifest.exec)
+    }
+    pub fn run(&self, args: &[String]) -> anyhow::Result<String> {
+        let output = Command::new(self.exec())
+            .args(&self.manifest.args)
+            .args(args)
+            .current_dir(&self.dir)
+            .env("PATH", paths().path_env())
+            .stdin(Stdio::null())
+            .output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            return Err(anyhow!(if stderr.is_empty() {
+                format!("Script exited with {}", output.status)
+            } else {
+                stderr
+            }));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+    pub fn spawn(&self, command: &[String]) -> anyhow::Result<()> {
+        let (program, args) = command
+            .split_first()
+            .ok_or(anyhow!("Empty script action command"))?;
+        let program = if program.starts_with("./") || program.starts_with("../") {
+            self.dir.join(program)
+        } else {
+            PathBuf::from(program)
+        };
+        Command::new(program)
+            .args(args)
+            .current_dir(&self.dir)
+            .env("PATH", paths().path_env())
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        Ok(())
+    }
+}
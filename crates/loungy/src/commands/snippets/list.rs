@@ -0,0 +1,367 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+// Reusable texts promoted from the clipboard history, listed in the root search and pasted with their placeholders filled in
+
+use std::{collections::HashMap, sync::OnceLock, time::Duration};
+
+use bonsaidb::{
+    core::schema::{Collection, SerializedCollection},
+    local::Database,
+};
+use gpui::{AnyView, App, Window};
+use jiff::Zoned;
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    archive::Archived,
+    command,
+    commands::{RootCommand, RootCommandBuilder, RootCommands},
+    components::{
+        form::{Form, Input, InputKind},
+        list::{Accessory, ItemBuilder, ListBuilder, ListItem},
+        shared::{Icon, Img},
+    },
+    db::LDb,
+    hotkey::{CommandAliases, HotkeyManager},
+    platform::move_cursor_left,
+    state::{CommandTrait, LAction, Shortcut, StateModel, StateViewBuilder, StateViewContext},
+    window::LWindow,
+};
+
+use crate::commands::{clipboard::push, paste};
+
+// Snippets get their own top level segment, so each one has a distinct CLI name
+const COMMAND_PREFIX: &str = "loungy::snippet::";
+// Time the focused application gets to handle the paste before the caret is moved
+const CURSOR_DELAY: Duration = Duration::from_millis(300);
+
+pub(crate) fn db() -> &'static Database {
+    static DB: OnceLock<Database> = OnceLock::new();
+    DB.get_or_init(LDb::init_collection::<Snippet>)
+}
+
+#[derive(Clone, Serialize, Deserialize, Collection)]
+#[collection(name = "clipboard.snippet")]
+pub(crate) struct Snippet {
+    // Id of the clipboard entry the snippet was created from
+    #[natural_id]
+    id: u64,
+    name: String,
+    keyword: String,
+    text: String,
+}
+
+impl Archived for Snippet {}
+
+// Fills in `{date}`, `{time}` and `{clipboard}`, along with how many characters end up after `{cursor}`
+fn expand(text: &str, clipboard: Option<&str>) -> (String, usize) {
+    let now = Zoned::now();
+    let text = text
+        .replace("{date}", &now.strftime("%Y-%m-%d").to_string())
+        .replace("{time}", &now.strftime("%H:%M").to_string())
+        .replace("{clipboard}", clipboard.unwrap_or_default());
+    match text.split_once("{cursor}") {
+        Some((before, after)) => {
+            let after = after.replace("{cursor}", "");
+            (format!("{}{}", before, after), after.chars().count())
+        }
+        None => (text, 0),
+    }
+}
+
+impl Snippet {
+    pub(crate) fn new(id: u64, name: impl ToString, text: impl ToString) -> Self {
+        Self {
+            id,
+            name: name.to_string(),
+            keyword: String::new(),
+            text: text.to_string(),
+        }
+    }
+    fn command_id(&self) -> String {
+        format!("{}{}", COMMAND_PREFIX, self.id)
+    }
+    fn paste(&self, cx: &mut App) {
+        let clipboard = cx.read_from_clipboard().and_then(|item| item.text());
+        let (text, cursor) = expand(&self.text, clipboard.as_deref());
+        paste(&text, cx);
+        if cursor == 0 {
+            return;
+        }
+        cx.spawn(async move |cx| {
+            while LWindow::is_open(cx) {
+                cx.background_executor()
+                    .timer(Duration::from_millis(10))
+                    .await;
+            }
+            cx.background_executor().timer(CURSOR_DELAY).await;
+            if let Err(err) = move_cursor_left(cursor) {
+                error!("Failed to move cursor: {:?}", err);
+            }
+        })
+        .detach();
+    }
+    fn command(&self) -> RootCommand {
+        let snippet = self.clone();
+        RootCommand::new(
+            self.command_id(),
+            self.name.clone(),
+            "Snippet",
+            Icon::TextQuote,
+            [self.keyword.clone()]
+                .into_iter()
+                .filter(|keyword| !keyword.is_empty())
+                .collect::<Vec<_>>(),
+            None,
+            move |_, cx| snippet.paste(cx),
+        )
+    }
+    fn delete(&self) -> anyhow::Result<()> {
+        if let Some(snippet) = Self::get(&self.id, db())? {
+            snippet.delete(db())?;
+        }
+        Ok(())
+    }
+    // Opens the form for the snippet, or for a new one created from the given entry
+    pub(crate) fn edit(self, cx: &mut App) {
+        let snippet = match Self::get(&self.id, db()) {
+            Ok(Some(snippet)) => snippet.contents,
+            _ => self,
+        };
+        push(SnippetFormBuilder { snippet }, cx);
+    }
+}
+
+fn snippets() -> Vec<Snippet> {
+    Snippet::all(db())
+        .query()
+        .inspect_err(|err| error!("Failed to load snippets: {:?}", err))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|snippet| snippet.contents)
+        .collect()
+}
+
+// Adds the snippet commands, leaving whatever already uses one of their ids alone
+pub fn insert_snippet_commands(commands: &mut HashMap<String, RootCommand>) {
+    for command in snippets().iter().map(Snippet::command) {
+        if commands.contains_key(&command.id) {
+            error!("Snippet command id already in use: {}", command.id);
+            continue;
+        }
+        commands.insert(command.id.clone(), command);
+    }
+}
+
+// Another snippet or a command alias already answering to the keyword
+fn keyword_taken(snippet: &Snippet) -> bool {
+    if snippet.keyword.is_empty() {
+        return false;
+    }
+    let keyword = snippet.keyword.to_lowercase();
+    snippets()
+        .iter()
+        .any(|other| other.id != snippet.id && other.keyword.to_lowercase() == keyword)
+        || CommandAliases::all_aliases()
+            .iter()
+            .any(|(id, alias)| *id != snippet.command_id() && alias.to_lowercase() == keyword)
+}
+
+// Re-registers the snippet commands after one was added, changed, removed or imported
+pub(crate) fn refresh(cx: &mut App) {
+    cx.update_global::<RootCommands, _>(|this, _| {
+        this.commands
+            .retain(|id, _| !id.starts_with(COMMAND_PREFIX));
+        insert_snippet_commands(&mut this.commands);
+    });
+    HotkeyManager::update(cx);
+}
+
+#[derive(Clone)]
+struct SnippetFormBuilder {
+    snippet: Snippet,
+}
+command!(SnippetFormBuilder);
+
+impl StateViewBuilder for SnippetFormBuilder {
+    fn build(&self, context: &mut StateViewContext, _: &mut Window, cx: &mut App) -> AnyView {
+        let snippet = self.snippet.clone();
+        Form::new(
+            vec![
+                Input::new(
+                    "name",
+                    "Name",
+                    InputKind::TextField {
+                        placeholder: "Shown in the root search".to_string(),
+                        value: snippet.name.clone(),
+                        validate: Some(|v| v.trim().is_empty().then_some("Name is required")),
+                        password: false,
+                    },
+                    cx,
+                ),
+                Input::new(
+                    "keyword",
+                    "Keyword",
+                    InputKind::TextField {
+                        placeholder: "Word such as \"sig\" to find the snippet by".to_string(),
+                        value: snippet.keyword.clone(),
+                        validate: Some(|v| {
+                            v.trim()
+                                .contains(char::is_whitespace)
+                                .then_some("Keyword must be a single word")
+                        }),
+                        password: false,
+                    },
+                    cx,
+                ),
+            ],
+            move |values, actions, cx| {
+                let mut snippet = snippet.clone();
+                snippet.name = values["name"].value::<String>().trim().to_string();
+                snippet.keyword = values["keyword"].value::<String>().trim().to_string();
+                if keyword_taken(&snippet) {
+                    actions.toast.error("Keyword already in use", cx);
+                    return;
+                }
+                if let Err(err) = snippet.clone().overwrite_into(&snippet.id, db()) {
+                    error!("Failed to save snippet: {:?}", err);
+                    actions.toast.error("Failed to save snippet", cx);
+                    return;
+                }
+                refresh(cx);
+                actions.toast.success("Snippet saved", cx);
+            },
+            context,
+            cx,
+        )
+        .into()
+    }
+}
+
+#[derive(Clone)]
+struct SnippetListBuilder;
+command!(SnippetListBuilder);
+
+impl StateViewBuilder for SnippetListBuilder {
+    fn build(&self, context: &mut StateViewContext, window: &mut Window, cx: &mut App) -> AnyView {
+        context.query.set_placeholder("Search snippets...", cx);
+        let list = ListBuilder::new().build(
+            |_, _, _| {
+                let mut snippets: Vec<Snippet> = Snippet::all(db())
+                    .query()?
+                    .into_iter()
+                    .map(|snippet| snippet.contents)
+                    .collect();
+                snippets.sort_by_key(|snippet| snippet.name.to_lowercase());
+                Ok(Some(
+                    snippets
+                        .into_iter()
+                        .map(|snippet| {
+                            let accessories = if snippet.keyword.is_empty() {
+                                vec![]
+                            } else {
+                                vec![Accessory::new(
+                                    &snippet.keyword,
+                                    Some(Img::default().icon(Icon::Tag)),
+                                )]
+                            };
+                            ItemBuilder::new(
+                                snippet.id,
+                                ListItem::new(
+                                    Some(Img::default().icon(Icon::TextQuote)),
+                                    snippet.name.clone(),
+                                    Some(snippet.text.replace('\n', " ")),
+                                    accessories,
+                                ),
+                            )
+                            .keywords(vec![snippet.name.clone(), snippet.keyword.clone()])
+                            .actions(vec![
+                                LAction::new(
+                                    Img::default().icon(Icon::ClipboardPaste),
+                                    "Paste",
+                                    None,
+                                    {
+                                        let snippet = snippet.clone();
+                                        move |_, cx| snippet.paste(cx)
+                                    },
+                                    false,
+                                ),
+                                LAction::new(
+                                    Img::default().icon(Icon::Pencil),
+                                    "Edit",
+                                    Some(Shortcut::new("e").cmd()),
+                                    {
+                                        let snippet = snippet.clone();
+                                        move |_, cx| snippet.clone().edit(cx)
+                                    },
+                                    false,
+                                ),
+                                LAction::new(
+                                    Img::default().icon(Icon::Trash),
+                                    "Delete",
+                                    None,
+                                    {
+                                        let snippet = snippet.clone();
+                                        move |actions, cx| {
+                                            if let Err(err) = snippet.delete() {
+                                                error!("Failed to delete snippet: {:?}", err);
+                                                actions.toast.error("Failed to delete snippet", cx);
+                                                return;
+                                            }
+                                            refresh(cx);
+                                            actions.toast.success("Snippet deleted", cx);
+                                        }
+                                    },
+                                    false,
+                                ),
+                            ])
+                            .build()
+                        })
+                        .collect(),
+                ))
+            },
+            context,
+            window,
+            cx,
+        );
+
+        // Snippets are saved and deleted from other views as well
+        cx.observe_global::<RootCommands>({
+            let list = list.downgrade();
+            move |cx| {
+                let _ = list.update(cx, |this, cx| this.update(true, cx));
+            }
+        })
+        .detach();
+        list.into()
+    }
+}
+
+pub struct SnippetsCommandBuilder;
+command!(SnippetsCommandBuilder);
+
+impl RootCommandBuilder for SnippetsCommandBuilder {
+    fn build(&self, window: &mut Window, _: &mut App) -> RootCommand {
+        RootCommand::new(
+            "snippets",
+            "Snippets",
+            "Clipboard",
+            Icon::TextQuote,
+            vec!["Text", "Template", "Expand"],
+            None,
+            |_, cx| {
+                StateModel::update(|this, cx| this.push(SnippetListBuilder, window, cx), cx);
+            },
+        )
+    }
+}
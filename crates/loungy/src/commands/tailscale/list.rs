@@ -76,7 +76,7 @@ impl StateViewBuilder for TailscaleListBuilder {
                         .eq(&this.actions.get_dropdown_value(cx));
                     let theme = cx.global::<LTheme>().clone();
                     let mut env: HashMap<String, String> = HashMap::new();
-                    env.insert("PATH".to_string(), paths().path_env.clone());
+                    env.insert("PATH".to_string(), paths().path_env().to_string());
                     let status = Command::new("tailscale")
                         .envs(env)
                         .arg("status")
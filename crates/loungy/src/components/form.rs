@@ -10,15 +10,15 @@
  */
 
 use crate::{
-    components::shared::{Icon, Img},
+    components::shared::{Icon, Img, ImgSize},
     query::{TextEvent, TextInputWeak},
     state::{LAction, LActions, Shortcut, StateViewContext},
     theme::LTheme,
 };
 use gpui::{
-    App, AppContext, Context, Entity, FontWeight, InteractiveElement, IntoElement, Keystroke,
-    ListAlignment, ListState, Modifiers, MouseButton, ParentElement, Render, Styled, Window, div,
-    list, px,
+    AnyElement, App, AppContext, Context, Entity, FontWeight, InteractiveElement, IntoElement,
+    Keystroke, ListAlignment, ListState, Modifiers, MouseButton, ParentElement, Render, Styled,
+    Window, div, list, px,
 };
 use std::{any::Any, collections::HashMap};
 
@@ -53,6 +53,8 @@ impl Input {
         let value: Box<dyn Any> = match self.kind.clone() {
             InputKind::TextField { value, .. } => Box::new(value),
             InputKind::Shortcut { value, .. } => Box::new(value),
+            InputKind::Dropdown { value, .. } => Box::new(value),
+            InputKind::Checkbox { value } => Box::new(value),
         };
         value.downcast_ref::<V>().unwrap().clone()
     }
@@ -133,6 +135,7 @@ impl Render for InputView {
                                 )
                                 .child("Recording...")
                                 .into_any_element(),
+                            kind => kind.render_choice(theme),
                         }
                     } else {
                         match self.inner.kind.clone() {
@@ -167,8 +170,13 @@ impl Render for InputView {
                                     "Record Hotkey".into_any_element()
                                 }
                             }
+                            kind => kind.render_choice(theme),
                         }
                     })
+                    .on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(|this, _, _, cx| this.step(true, cx)),
+                    )
                     .w_1_2()
                     .p_2()
                     .border_1()
@@ -216,8 +224,32 @@ impl InputView {
                 self.input.set_text(value, cx);
             }
             InputKind::Shortcut { .. } => self.input.set_text("Record hotkey", cx),
+            InputKind::Dropdown { .. } | InputKind::Checkbox { .. } => self.input.set_text("", cx),
         };
     }
+    // Picks the next or previous option, or flips the checkbox
+    fn step(&mut self, forward: bool, cx: &mut Context<Self>) {
+        match &mut self.inner.kind {
+            InputKind::Dropdown { value, options } => {
+                if options.is_empty() {
+                    return;
+                }
+                let index = options
+                    .iter()
+                    .position(|(option, _)| option.eq(value))
+                    .unwrap_or(0);
+                let index = if forward {
+                    (index + 1) % options.len()
+                } else {
+                    (index + options.len() - 1) % options.len()
+                };
+                *value = options[index].0.clone();
+            }
+            InputKind::Checkbox { value } => *value = !*value,
+            _ => return,
+        }
+        cx.notify();
+    }
     pub fn on_blur(&mut self, _: &mut Context<Self>) {
         self.inner.show_error = true;
         self.inner.validate();
@@ -277,6 +309,18 @@ impl InputView {
                     }
                 }
             }
+            InputKind::Dropdown { .. } | InputKind::Checkbox { .. } => match event {
+                TextEvent::KeyDown(e) if e.keystroke.modifiers == Modifiers::default() => {
+                    match e.keystroke.key.as_str() {
+                        "space" | "down" => self.step(true, cx),
+                        "up" => self.step(false, cx),
+                        _ => {}
+                    }
+                }
+                // Nothing is typed into a choice
+                TextEvent::Input { text } if !text.is_empty() => self.input.set_text("", cx),
+                _ => {}
+            },
         }
         if let TextEvent::KeyDown(e) = event {
             if Shortcut::new("tab").shift().get().eq(&e.keystroke) {
@@ -353,6 +397,54 @@ pub enum InputKind {
         value: Option<Shortcut>,
         tmp: Option<Shortcut>,
     },
+    // `options` are value and label pairs
+    Dropdown {
+        value: String,
+        options: Vec<(String, String)>,
+    },
+    Checkbox {
+        value: bool,
+    },
+}
+
+impl InputKind {
+    fn render_choice(self, theme: &LTheme) -> AnyElement {
+        match self {
+            InputKind::Dropdown { value, options } => div()
+                .flex()
+                .items_center()
+                .justify_between()
+                .child(
+                    options
+                        .into_iter()
+                        .find(|(option, _)| option.eq(&value))
+                        .map(|(_, label)| label)
+                        .unwrap_or(value),
+                )
+                .child(Img::default().icon(Icon::ChevronsUpDown).size(ImgSize::XS))
+                .into_any_element(),
+            InputKind::Checkbox { value } => div()
+                .flex()
+                .items_center()
+                .gap_2()
+                .child(
+                    div()
+                        .size_4()
+                        .flex()
+                        .items_center()
+                        .justify_center()
+                        .rounded_sm()
+                        .border_1()
+                        .border_color(theme.surface2)
+                        .children(
+                            value.then(|| Img::default().icon(Icon::Check).size(ImgSize::XS)),
+                        ),
+                )
+                .child(if value { "Yes" } else { "No" })
+                .into_any_element(),
+            _ => div().into_any_element(),
+        }
+    }
 }
 
 pub trait SubmitFn: Fn(HashMap<String, Input>, &mut LActions, &mut App) {
@@ -163,13 +163,17 @@ pub struct ItemBuilder {
     meta: Option<AnyEntity>,
 }
 
+// The id a list item gets for the given `ItemBuilder::new` id
+pub fn item_id(id: impl Hash) -> u64 {
+    let mut s = DefaultHasher::new();
+    id.hash(&mut s);
+    s.finish()
+}
+
 impl ItemBuilder {
     pub fn new(id: impl Hash, component: impl ItemComponent + 'static) -> Self {
-        let mut s = DefaultHasher::new();
-        id.hash(&mut s);
-        let id = s.finish();
         Self {
-            id,
+            id: item_id(id),
             preview: None,
             actions: vec![],
             weight: None,
@@ -250,6 +254,9 @@ impl Item {
     pub fn get_keywords(&self) -> Vec<SharedString> {
         self.keywords.clone()
     }
+    pub fn get_id(&self) -> u64 {
+        self.id
+    }
 }
 
 pub trait Preview: Fn(&mut App) -> StateItem + 'static {}
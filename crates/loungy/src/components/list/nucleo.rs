@@ -20,6 +20,13 @@ use crate::state::LazyMutex;
 pub static MATCHER: LazyMutex<nucleo::Matcher> = LazyMutex::new(nucleo::Matcher::default);
 
 pub fn fuzzy_match<T: Score>(pattern: &str, items: Vec<T>, path: bool) -> Vec<T> {
+    let mut items = fuzzy_score(pattern, items, path);
+    items.sort_by_key(|(_, score)| Reverse(*score));
+    items.into_iter().map(|item| item.0).collect()
+}
+
+// Unsorted matches with their weighted score, for callers that blend in their own ranking
+pub fn fuzzy_score<T: Score>(pattern: &str, items: Vec<T>, path: bool) -> Vec<(T, u16)> {
     let mut matcher = MATCHER.lock();
     matcher.config = Config::DEFAULT;
     if path {
@@ -33,12 +40,10 @@ pub fn fuzzy_match<T: Score>(pattern: &str, items: Vec<T>, path: bool) -> Vec<T>
         false,
     );
     let mut buf = Vec::new();
-    let mut items: Vec<(T, u16)> = items
+    items
         .into_iter()
         .filter_map(|item| item.score(&pattern, &mut buf, &mut matcher))
-        .collect();
-    items.sort_by_key(|(_, score)| Reverse(*score));
-    items.into_iter().map(|item| item.0).collect()
+        .collect()
 }
 
 pub trait Score {
@@ -0,0 +1,141 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+// Optional `config.toml`, meant to be kept in dotfiles. Anything set in here wins over the values stored in the database.
+
+use std::{collections::HashMap, path::PathBuf, sync::OnceLock, time::Duration};
+
+use gpui::{App, BorrowAppContext};
+use log::error;
+use notify::Watcher;
+use notify_debouncer_full::new_debouncer;
+use parking_lot::{RwLock, RwLockReadGuard};
+use serde::{Deserialize, de::DeserializeOwned};
+
+use crate::{
+    commands::RootCommands,
+    hotkey::{CommandAliases, HotkeyManager},
+    paths::paths,
+    state::StateModel,
+    theme::LTheme,
+};
+
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct ThemeConfig {
+    pub light: Option<String>,
+    pub dark: Option<String>,
+}
+
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct Config {
+    pub theme: ThemeConfig,
+    // Command id to hotkey, `loungy::toggle` being the window toggle
+    pub hotkeys: HashMap<String, String>,
+    // Command id to alias
+    pub aliases: HashMap<String, String>,
+    // Command id to whether it's enabled
+    pub commands: HashMap<String, bool>,
+    // Free form options, keyed by command id
+    pub options: HashMap<String, toml::Table>,
+}
+
+fn store() -> &'static RwLock<Config> {
+    static CONFIG: OnceLock<RwLock<Config>> = OnceLock::new();
+    CONFIG.get_or_init(|| {
+        RwLock::new(Config::load().unwrap_or_else(|err| {
+            error!("Failed to load config: {}", err);
+            Config::default()
+        }))
+    })
+}
+
+pub fn config() -> RwLockReadGuard<'static, Config> {
+    store().read()
+}
+
+impl Config {
+    pub fn path() -> PathBuf {
+        paths().config.join("config.toml")
+    }
+    fn load() -> anyhow::Result<Self> {
+        let path = Self::path();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(toml::from_str(&std::fs::read_to_string(path)?)?)
+    }
+    pub fn option<T: DeserializeOwned>(id: &str, key: &str) -> Option<T> {
+        let value = config().options.get(id)?.get(key)?.clone();
+        value
+            .try_into()
+            .inspect_err(|err| error!("Invalid option {} for {}: {}", key, id, err))
+            .ok()
+    }
+    // Reloads the file whenever it changes and applies it right away
+    pub fn watch(cx: &mut App) {
+        cx.spawn(async move |cx| {
+            let (tx, rx) = std::sync::mpsc::channel();
+            let mut debouncer = match new_debouncer(Duration::from_secs(1), None, tx) {
+                Ok(debouncer) => debouncer,
+                Err(err) => {
+                    error!("Failed to watch config: {:?}", err);
+                    return;
+                }
+            };
+            // Watch the folder, so the file can be created, removed or replaced by editors
+            let root = paths().config.clone();
+            if !root.exists() {
+                let _ = std::fs::create_dir_all(&root);
+            }
+            let _ = debouncer
+                .watcher()
+                .watch(&root, notify::RecursiveMode::NonRecursive);
+
+            let path = Self::path();
+            loop {
+                if let Ok(Ok(events)) = rx.try_recv() {
+                    if events
+                        .iter()
+                        .any(|event| event.paths.iter().any(|p| p.eq(&path)))
+                    {
+                        let _ = cx.update(Self::reload);
+                    }
+                }
+                cx.background_executor().timer(Duration::from_secs(1)).await;
+            }
+        })
+        .detach();
+    }
+    fn reload(cx: &mut App) {
+        match Self::load() {
+            Ok(config) => {
+                *store().write() = config;
+            }
+            Err(err) => {
+                // Keep the last working config around instead of falling back to the defaults
+                error!("Failed to load config: {}", err);
+                StateModel::toast_error(format!("Invalid config.toml: {}", err), cx);
+                return;
+            }
+        }
+        let theme = LTheme::mode(cx.window_appearance());
+        cx.update_global::<LTheme, _>(|this, _| {
+            *this = theme;
+        });
+        CommandAliases::reload(cx);
+        cx.update_global::<RootCommands, _>(|_, _| {});
+        HotkeyManager::reload_toggle(cx);
+        HotkeyManager::update(cx);
+        cx.refresh();
+    }
+}
@@ -0,0 +1,380 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+// Mapping between gpui keystrokes, which use key names, and global hotkeys, which use physical key codes
+
+use std::str::FromStr;
+
+use global_hotkey::hotkey::{Code, HotKey, Modifiers};
+use gpui::Keystroke;
+
+const LETTERS: [Code; 26] = [
+    Code::KeyA,
+    Code::KeyB,
+    Code::KeyC,
+    Code::KeyD,
+    Code::KeyE,
+    Code::KeyF,
+    Code::KeyG,
+    Code::KeyH,
+    Code::KeyI,
+    Code::KeyJ,
+    Code::KeyK,
+    Code::KeyL,
+    Code::KeyM,
+    Code::KeyN,
+    Code::KeyO,
+    Code::KeyP,
+    Code::KeyQ,
+    Code::KeyR,
+    Code::KeyS,
+    Code::KeyT,
+    Code::KeyU,
+    Code::KeyV,
+    Code::KeyW,
+    Code::KeyX,
+    Code::KeyY,
+    Code::KeyZ,
+];
+
+const DIGITS: [Code; 10] = [
+    Code::Digit0,
+    Code::Digit1,
+    Code::Digit2,
+    Code::Digit3,
+    Code::Digit4,
+    Code::Digit5,
+    Code::Digit6,
+    Code::Digit7,
+    Code::Digit8,
+    Code::Digit9,
+];
+
+const FUNCTION_KEYS: [Code; 24] = [
+    Code::F1,
+    Code::F2,
+    Code::F3,
+    Code::F4,
+    Code::F5,
+    Code::F6,
+    Code::F7,
+    Code::F8,
+    Code::F9,
+    Code::F10,
+    Code::F11,
+    Code::F12,
+    Code::F13,
+    Code::F14,
+    Code::F15,
+    Code::F16,
+    Code::F17,
+    Code::F18,
+    Code::F19,
+    Code::F20,
+    Code::F21,
+    Code::F22,
+    Code::F23,
+    Code::F24,
+];
+
+// Named keys, used in both directions
+const KEYS: &[(&str, Code)] = &[
+    ("space", Code::Space),
+    ("enter", Code::Enter),
+    ("tab", Code::Tab),
+    ("backspace", Code::Backspace),
+    ("escape", Code::Escape),
+    ("delete", Code::Delete),
+    ("insert", Code::Insert),
+    ("home", Code::Home),
+    ("end", Code::End),
+    ("pageup", Code::PageUp),
+    ("pagedown", Code::PageDown),
+    ("up", Code::ArrowUp),
+    ("down", Code::ArrowDown),
+    ("left", Code::ArrowLeft),
+    ("right", Code::ArrowRight),
+    ("capslock", Code::CapsLock),
+    ("printscreen", Code::PrintScreen),
+    ("scrolllock", Code::ScrollLock),
+    ("pause", Code::Pause),
+    ("numlock", Code::NumLock),
+    // Punctuation
+    ("`", Code::Backquote),
+    ("-", Code::Minus),
+    ("=", Code::Equal),
+    ("[", Code::BracketLeft),
+    ("]", Code::BracketRight),
+    ("\\", Code::Backslash),
+    (";", Code::Semicolon),
+    ("'", Code::Quote),
+    (",", Code::Comma),
+    (".", Code::Period),
+    ("/", Code::Slash),
+    // Numpad
+    ("numpad0", Code::Numpad0),
+    ("numpad1", Code::Numpad1),
+    ("numpad2", Code::Numpad2),
+    ("numpad3", Code::Numpad3),
+    ("numpad4", Code::Numpad4),
+    ("numpad5", Code::Numpad5),
+    ("numpad6", Code::Numpad6),
+    ("numpad7", Code::Numpad7),
+    ("numpad8", Code::Numpad8),
+    ("numpad9", Code::Numpad9),
+    ("numpadadd", Code::NumpadAdd),
+    ("numpadsubtract", Code::NumpadSubtract),
+    ("numpadmultiply", Code::NumpadMultiply),
+    ("numpaddivide", Code::NumpadDivide),
+    ("numpaddecimal", Code::NumpadDecimal),
+    ("numpadenter", Code::NumpadEnter),
+    ("numpadequal", Code::NumpadEqual),
+    // Media
+    ("volumeup", Code::AudioVolumeUp),
+    ("volumedown", Code::AudioVolumeDown),
+    ("mute", Code::AudioVolumeMute),
+    ("mediaplaypause", Code::MediaPlayPause),
+    ("mediastop", Code::MediaStop),
+    ("medianext", Code::MediaTrackNext),
+    ("mediaprevious", Code::MediaTrackPrevious),
+];
+
+// Characters gpui reports for shifted keys, assuming a US layout
+const SHIFTED: &[(&str, &str)] = &[
+    ("~", "`"),
+    ("!", "1"),
+    ("@", "2"),
+    ("#", "3"),
+    ("$", "4"),
+    ("%", "5"),
+    ("^", "6"),
+    ("&", "7"),
+    ("*", "8"),
+    ("(", "9"),
+    (")", "0"),
+    ("_", "-"),
+    ("+", "="),
+    ("{", "["),
+    ("}", "]"),
+    ("|", "\\"),
+    (":", ";"),
+    ("\"", "'"),
+    ("<", ","),
+    (">", "."),
+    ("?", "/"),
+];
+
+fn key_to_code(key: &str) -> Option<Code> {
+    let mut chars = key.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        if c.is_ascii_alphabetic() {
+            return Some(LETTERS[(c.to_ascii_lowercase() as u8 - b'a') as usize]);
+        }
+        if c.is_ascii_digit() {
+            return Some(DIGITS[(c as u8 - b'0') as usize]);
+        }
+    }
+    if let Some(n) = key.strip_prefix('f').and_then(|n| n.parse::<usize>().ok()) {
+        return FUNCTION_KEYS.get(n.checked_sub(1)?).copied();
+    }
+    KEYS.iter()
+        .find(|(name, _)| name.eq(&key))
+        .map(|(_, code)| *code)
+}
+
+fn code_to_key(code: Code) -> Option<String> {
+    if let Some(i) = LETTERS.iter().position(|c| *c == code) {
+        return Some(((b'a' + i as u8) as char).to_string());
+    }
+    if let Some(i) = DIGITS.iter().position(|c| *c == code) {
+        return Some(i.to_string());
+    }
+    if let Some(i) = FUNCTION_KEYS.iter().position(|c| *c == code) {
+        return Some(format!("f{}", i + 1));
+    }
+    KEYS.iter()
+        .find(|(_, c)| *c == code)
+        .map(|(name, _)| name.to_string())
+}
+
+// `None` if the key has no physical key code
+pub fn keystroke_to_hotkey(keystroke: &Keystroke) -> Option<HotKey> {
+    let mut key = keystroke.key.as_str();
+    let mut shift = keystroke.modifiers.shift;
+    if let Some((_, unshifted)) = SHIFTED.iter().find(|(shifted, _)| shifted.eq(&key)) {
+        key = unshifted;
+        shift = true;
+    }
+    if key.len() == 1 && key.chars().all(|c| c.is_ascii_uppercase()) {
+        shift = true;
+    }
+    let code = key_to_code(&key.to_lowercase())?;
+
+    let mut mods = Modifiers::empty();
+    mods.set(Modifiers::SHIFT, shift);
+    mods.set(Modifiers::CONTROL, keystroke.modifiers.control);
+    mods.set(Modifiers::ALT, keystroke.modifiers.alt);
+    mods.set(Modifiers::META, keystroke.modifiers.platform);
+    Some(HotKey::new(Some(mods), code))
+}
+
+pub fn hotkey_to_keystroke(hotkey: &HotKey) -> Keystroke {
+    let mut keystroke = Keystroke::default();
+    keystroke.modifiers.shift = hotkey.mods.contains(Modifiers::SHIFT);
+    keystroke.modifiers.control = hotkey.mods.contains(Modifiers::CONTROL);
+    keystroke.modifiers.alt = hotkey.mods.contains(Modifiers::ALT);
+    keystroke.modifiers.platform = hotkey.mods.intersects(Modifiers::SUPER | Modifiers::META);
+    keystroke.key =
+        code_to_key(hotkey.key).unwrap_or_else(|| hotkey.key.to_string().to_lowercase());
+    keystroke
+}
+
+// Stored form of a hotkey, e.g. `control+super+KeyV`
+pub fn hotkey_to_string(hotkey: &HotKey) -> String {
+    let mut tokens = vec![];
+    if hotkey.mods.contains(Modifiers::SHIFT) {
+        tokens.push("shift".to_string());
+    }
+    if hotkey.mods.contains(Modifiers::CONTROL) {
+        tokens.push("control".to_string());
+    }
+    if hotkey.mods.contains(Modifiers::ALT) {
+        tokens.push("alt".to_string());
+    }
+    if hotkey.mods.intersects(Modifiers::SUPER | Modifiers::META) {
+        tokens.push("super".to_string());
+    }
+    tokens.push(hotkey.key.to_string());
+    tokens.join("+")
+}
+
+// Also accepts the older `alt+command+control+shift+key` format using gpui key names
+pub fn string_to_hotkey(value: &str) -> Option<HotKey> {
+    let mut tokens: Vec<&str> = value.split('+').collect();
+    let key = tokens.pop()?;
+    let mut mods = Modifiers::empty();
+    for token in tokens {
+        match token.to_lowercase().as_str() {
+            "shift" => mods.insert(Modifiers::SHIFT),
+            "control" | "ctrl" => mods.insert(Modifiers::CONTROL),
+            "alt" | "option" => mods.insert(Modifiers::ALT),
+            "super" | "command" | "cmd" | "meta" => mods.insert(Modifiers::META),
+            _ => return None,
+        }
+    }
+    let code = Code::from_str(key)
+        .ok()
+        .filter(|code| *code != Code::Unidentified)
+        .or_else(|| key_to_code(&key.to_lowercase()))?;
+    Some(HotKey::new(Some(mods), code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keystroke(key: &str) -> Keystroke {
+        Keystroke {
+            key: key.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn round_trip(keystroke: &Keystroke, code: Code, mods: Modifiers) {
+        let hotkey = keystroke_to_hotkey(keystroke).unwrap();
+        assert_eq!(hotkey, HotKey::new(Some(mods), code));
+        assert_eq!(hotkey_to_keystroke(&hotkey), *keystroke);
+        assert_eq!(string_to_hotkey(&hotkey_to_string(&hotkey)), Some(hotkey));
+    }
+
+    #[test]
+    fn letters() {
+        for (i, code) in LETTERS.iter().enumerate() {
+            let key = ((b'a' + i as u8) as char).to_string();
+            round_trip(&keystroke(&key), *code, Modifiers::empty());
+        }
+    }
+
+    #[test]
+    fn digits() {
+        for (i, code) in DIGITS.iter().enumerate() {
+            round_trip(&keystroke(&i.to_string()), *code, Modifiers::empty());
+        }
+    }
+
+    #[test]
+    fn function_keys() {
+        for (i, code) in FUNCTION_KEYS.iter().enumerate() {
+            round_trip(
+                &keystroke(&format!("f{}", i + 1)),
+                *code,
+                Modifiers::empty(),
+            );
+        }
+    }
+
+    #[test]
+    fn punctuation() {
+        for (key, code) in [
+            ("`", Code::Backquote),
+            ("-", Code::Minus),
+            ("=", Code::Equal),
+            ("[", Code::BracketLeft),
+            ("]", Code::BracketRight),
+            ("\\", Code::Backslash),
+            (";", Code::Semicolon),
+            ("'", Code::Quote),
+            (",", Code::Comma),
+            (".", Code::Period),
+            ("/", Code::Slash),
+        ] {
+            round_trip(&keystroke(key), code, Modifiers::empty());
+        }
+    }
+
+    #[test]
+    fn shifted_punctuation() {
+        let hotkey = keystroke_to_hotkey(&keystroke("?")).unwrap();
+        assert_eq!(hotkey, HotKey::new(Some(Modifiers::SHIFT), Code::Slash));
+    }
+
+    #[test]
+    fn modifiers() {
+        let mut shift = keystroke("k");
+        shift.modifiers.shift = true;
+        round_trip(&shift, Code::KeyK, Modifiers::SHIFT);
+
+        let mut control = keystroke("k");
+        control.modifiers.control = true;
+        round_trip(&control, Code::KeyK, Modifiers::CONTROL);
+
+        let mut alt = keystroke("k");
+        alt.modifiers.alt = true;
+        round_trip(&alt, Code::KeyK, Modifiers::ALT);
+
+        let mut platform = keystroke("k");
+        platform.modifiers.platform = true;
+        round_trip(&platform, Code::KeyK, Modifiers::META);
+    }
+
+    #[test]
+    fn default_toggle() {
+        let hotkey = string_to_hotkey("alt+command+control+space").unwrap();
+        assert_eq!(
+            hotkey,
+            HotKey::new(
+                Some(Modifiers::ALT | Modifiers::CONTROL | Modifiers::META),
+                Code::Space
+            )
+        );
+        assert_eq!(string_to_hotkey(&hotkey_to_string(&hotkey)), Some(hotkey));
+    }
+}
@@ -0,0 +1,422 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+mod keys;
+
+use std::{collections::HashMap, sync::OnceLock, time::Duration};
+
+use bonsaidb::{
+    core::schema::{Collection, SerializedCollection},
+    local::Database,
+};
+use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager, hotkey::HotKey};
+use gpui::{App, BorrowAppContext, Global, Keystroke};
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    archive::Archived,
+    commands::{RootCommand, RootCommands, root::preferences::Preferences},
+    config::{Config, config},
+    db::LDb,
+    state::{LActions, StateModel},
+    window::LWindow,
+};
+
+pub use keys::{hotkey_to_keystroke, hotkey_to_string, keystroke_to_hotkey, string_to_hotkey};
+
+// `CommandHotkeys` id of the hotkey toggling the window
+pub const TOGGLE_ID: &str = "loungy::toggle";
+pub const DEFAULT_TOGGLE: &str = "alt+command+control+space";
+
+pub struct HotkeyManager {
+    manager: GlobalHotKeyManager,
+    // `None` if the configured toggle hotkey couldn't be registered
+    toggle: Option<HotKey>,
+    hotkeys: Vec<HotKey>,
+    map: HashMap<u32, RootCommand>,
+    // Aliases by command id, the root search resolves them on every keystroke
+    aliases: HashMap<String, String>,
+}
+
+impl Global for HotkeyManager {}
+
+pub(crate) fn db() -> &'static Database {
+    static DB: OnceLock<Database> = OnceLock::new();
+    DB.get_or_init(LDb::init_collection::<CommandHotkeys>)
+}
+
+impl HotkeyManager {
+    // Stored hotkeys by command id, with the ones from the config file taking precedence
+    fn stored() -> HashMap<String, String> {
+        let mut hotkeys: HashMap<String, String> = CommandHotkeys::all(db())
+            .query()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|hk| (hk.contents.id, hk.contents.hotkey))
+            .collect();
+        hotkeys.extend(config().hotkeys.clone());
+        hotkeys
+    }
+    fn stored_toggle() -> String {
+        Self::stored()
+            .remove(TOGGLE_ID)
+            .unwrap_or(DEFAULT_TOGGLE.to_string())
+    }
+    pub fn init(cx: &mut App) {
+        let manager = GlobalHotKeyManager::new().unwrap();
+        let receiver = GlobalHotKeyEvent::receiver().clone();
+        let value = Self::stored_toggle();
+        let toggle = string_to_hotkey(&value)
+            .ok_or(anyhow::anyhow!("Invalid hotkey"))
+            .and_then(|hotkey| {
+                manager.register(hotkey)?;
+                Ok(hotkey)
+            });
+
+        cx.set_global::<HotkeyManager>(HotkeyManager {
+            manager,
+            toggle: toggle.as_ref().ok().copied(),
+            hotkeys: vec![],
+            map: HashMap::new(),
+            aliases: CommandAliases::all_aliases(),
+        });
+
+        if let Err(err) = toggle {
+            error!("Failed to register toggle hotkey {}: {}", value, err);
+            // Without a working hotkey the window would never show up, so open it right away to tell
+            cx.defer(move |cx| {
+                LWindow::open(cx);
+                StateModel::toast_error(
+                    format!(
+                        "Failed to register hotkey {}, change it in the preferences",
+                        value
+                    ),
+                    cx,
+                );
+            });
+        }
+
+        Self::update(cx);
+        cx.spawn(async move |cx| {
+            loop {
+                if let Ok(event) = receiver.try_recv() {
+                    if event.state == global_hotkey::HotKeyState::Released {
+                        let _ = cx.update_global::<HotkeyManager, _>(|manager, cx| {
+                            if let Some(command) = manager.map.get(&event.id) {
+                                let state = cx.global::<StateModel>();
+                                let state = state.inner.read(cx);
+                                let mut is_active = false;
+                                if let Some(active) = state.stack.last() {
+                                    is_active = active.id.eq(&command.id);
+                                };
+                                if !is_active {
+                                    StateModel::update(
+                                        |this, cx| {
+                                            this.reset(cx);
+                                        },
+                                        cx,
+                                    );
+                                    (command.action)(&mut LActions::default(cx), cx);
+                                    LWindow::open(cx);
+                                } else {
+                                    LWindow::toggle(cx);
+                                }
+                            } else {
+                                LWindow::toggle(cx);
+                            }
+                        });
+                    }
+                }
+                cx.background_executor()
+                    .timer(Duration::from_millis(50))
+                    .await;
+            }
+        })
+        .detach();
+    }
+    pub fn update(cx: &mut App) {
+        let failed = cx.update_global::<HotkeyManager, _>(|manager, cx| {
+            let commands = cx.global::<RootCommands>();
+            let hotkeys = Self::stored();
+            let _ = manager.manager.unregister_all(&manager.hotkeys);
+            manager.hotkeys.clear();
+            manager.map.clear();
+            let mut failed = vec![];
+            for (id, hotkey) in hotkeys {
+                if !Preferences::is_enabled(&id) {
+                    continue;
+                }
+                let known = commands.commands.get(id.as_str());
+                if let Some(known) = known {
+                    let registered = string_to_hotkey(&hotkey)
+                        .ok_or(anyhow::anyhow!("Invalid hotkey"))
+                        .and_then(|hk| {
+                            manager.manager.register(hk)?;
+                            Ok(hk)
+                        });
+                    match registered {
+                        Ok(hk) => {
+                            manager.hotkeys.push(hk);
+                            manager.map.insert(hk.id(), known.clone());
+                        }
+                        Err(err) => {
+                            error!("Failed to register hotkey {}: {}", hotkey, err);
+                            failed.push(known.title.clone());
+                        }
+                    }
+                }
+            }
+            failed
+        });
+        if !failed.is_empty() {
+            StateModel::toast_error(
+                format!("Failed to register hotkey for {}", failed.join(", ")),
+                cx,
+            );
+        }
+    }
+    // Title of whatever else the hotkey is already assigned to
+    fn assigned_to(id: &str, hotkey: &HotKey, cx: &App) -> Option<String> {
+        // The toggle might still be the default one, which isn't stored
+        if id.ne(TOGGLE_ID) && cx.global::<HotkeyManager>().toggle == Some(*hotkey) {
+            return Some("Toggle Loungy".to_string());
+        }
+        let commands = cx.global::<RootCommands>();
+        Self::stored()
+            .into_iter()
+            .filter(|(other, _)| other.ne(id))
+            .filter(|(_, other)| {
+                string_to_hotkey(other).is_some_and(|other| other.id() == hotkey.id())
+            })
+            .find_map(|(other, _)| {
+                if other.eq(TOGGLE_ID) {
+                    Some("Toggle Loungy".to_string())
+                } else {
+                    commands
+                        .commands
+                        .get(&other)
+                        .map(|command| command.title.clone())
+                }
+            })
+    }
+    // Set in the config file, which can't be changed from within Loungy
+    pub fn is_configured(id: &str) -> bool {
+        config().hotkeys.contains_key(id)
+    }
+    // Converts the keystroke and makes sure it's free and accepted by the OS, before anything gets stored
+    fn check(id: &str, keystroke: &Keystroke, cx: &mut App) -> anyhow::Result<HotKey> {
+        if Self::is_configured(id) {
+            return Err(anyhow::anyhow!(
+                "Hotkey is set in {}",
+                Config::path().display()
+            ));
+        }
+        let hotkey = keystroke_to_hotkey(keystroke)
+            .ok_or(anyhow::anyhow!("Unsupported key: {}", keystroke.key))?;
+        if let Some(title) = Self::assigned_to(id, &hotkey, cx) {
+            return Err(anyhow::anyhow!("Hotkey already assigned to {}", title));
+        }
+        cx.update_global::<HotkeyManager, _>(|manager, _| {
+            // Registered by us already, so it has to belong to this very command
+            if manager.map.contains_key(&hotkey.id()) || manager.toggle == Some(hotkey) {
+                return Ok(hotkey);
+            }
+            manager
+                .manager
+                .register(hotkey)
+                .map_err(|err| anyhow::anyhow!("Hotkey rejected by the system: {}", err))?;
+            let _ = manager.manager.unregister(hotkey);
+            Ok(hotkey)
+        })
+    }
+    pub fn set(id: &str, keystroke: Keystroke, cx: &mut App) -> anyhow::Result<()> {
+        let hotkey = Self::check(id, &keystroke, cx)?;
+
+        CommandHotkeys {
+            id: id.to_string(),
+            hotkey: hotkey_to_string(&hotkey),
+        }
+        .overwrite_into(&id.to_string(), db())?;
+        Self::update(cx);
+        Ok(())
+    }
+    pub fn unset(id: &str, cx: &mut App) -> anyhow::Result<()> {
+        if let Some(hk) = CommandHotkeys::get(&id.to_string(), db())? {
+            hk.delete(db())?;
+        }
+        Self::update(cx);
+        Ok(())
+    }
+    pub fn get(id: &str) -> Option<Keystroke> {
+        let hotkey = Self::stored().remove(id)?;
+        string_to_hotkey(&hotkey).map(|hotkey| hotkey_to_keystroke(&hotkey))
+    }
+    // Swaps the global toggle hotkey, keeping the previous one if the new one can't be registered
+    pub fn set_toggle(keystroke: Keystroke, cx: &mut App) -> anyhow::Result<()> {
+        let hotkey = Self::check(TOGGLE_ID, &keystroke, cx)?;
+        cx.update_global::<HotkeyManager, _>(|manager, _| {
+            if manager.toggle == Some(hotkey) {
+                return Ok(());
+            }
+            manager.manager.register(hotkey)?;
+            if let Some(toggle) = manager.toggle.replace(hotkey) {
+                let _ = manager.manager.unregister(toggle);
+            }
+            anyhow::Ok(())
+        })?;
+        CommandHotkeys {
+            id: TOGGLE_ID.to_string(),
+            hotkey: hotkey_to_string(&hotkey),
+        }
+        .overwrite_into(&TOGGLE_ID.to_string(), db())?;
+        Ok(())
+    }
+    // Re-registers the toggle after the config file changed, without persisting it
+    pub fn reload_toggle(cx: &mut App) {
+        let value = Self::stored_toggle();
+        let Some(hotkey) = string_to_hotkey(&value) else {
+            StateModel::toast_error(format!("Invalid hotkey {}", value), cx);
+            return;
+        };
+        let result = cx.update_global::<HotkeyManager, _>(|manager, _| {
+            if manager.toggle == Some(hotkey) {
+                return Ok(());
+            }
+            manager.manager.register(hotkey)?;
+            if let Some(toggle) = manager.toggle.replace(hotkey) {
+                let _ = manager.manager.unregister(toggle);
+            }
+            anyhow::Ok(())
+        });
+        if let Err(err) = result {
+            error!("Failed to register toggle hotkey {}: {}", value, err);
+            StateModel::toast_error(format!("Failed to register hotkey {}", value), cx);
+        }
+    }
+    pub fn is_toggle(keystroke: &Keystroke) -> bool {
+        let toggle = string_to_hotkey(&Self::stored_toggle());
+        keystroke_to_hotkey(keystroke).is_some_and(|hotkey| toggle == Some(hotkey))
+    }
+    pub fn get_toggle() -> Keystroke {
+        Self::get(TOGGLE_ID).unwrap_or_else(|| {
+            string_to_hotkey(DEFAULT_TOGGLE)
+                .map(|hotkey| hotkey_to_keystroke(&hotkey))
+                .unwrap_or_default()
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Collection, Debug)]
+#[collection(name = "command-hotkeys")]
+pub struct CommandHotkeys {
+    #[natural_id]
+    id: String,
+    hotkey: String,
+}
+
+impl Archived for CommandHotkeys {}
+
+pub(crate) fn db_aliases() -> &'static Database {
+    static DB: OnceLock<Database> = OnceLock::new();
+    DB.get_or_init(LDb::init_collection::<CommandAliases>)
+}
+
+// Query prefixes like `cb` that open a command straight from the root search
+#[derive(Serialize, Deserialize, Collection, Debug)]
+#[collection(name = "command-aliases")]
+pub struct CommandAliases {
+    #[natural_id]
+    id: String,
+    alias: String,
+}
+
+impl Archived for CommandAliases {}
+
+impl CommandAliases {
+    // Aliases from the config file replace the stored ones, both by command and by alias
+    pub fn all_aliases() -> HashMap<String, String> {
+        let overrides = config().aliases.clone();
+        let mut aliases: HashMap<String, String> = CommandAliases::all(db_aliases())
+            .query()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|alias| (alias.contents.id, alias.contents.alias))
+            .filter(|(_, alias)| !overrides.values().any(|other| other.eq(alias)))
+            .collect();
+        aliases.extend(overrides);
+        aliases
+    }
+    pub fn alias(id: &str) -> Option<String> {
+        Self::all_aliases().remove(id)
+    }
+    // Picks up aliases after they were saved, imported or changed in the config file
+    pub fn reload(cx: &mut App) {
+        let aliases = Self::all_aliases();
+        cx.update_global::<HotkeyManager, _>(|manager, _| manager.aliases = aliases);
+    }
+    pub fn set_alias(id: &str, alias: &str, cx: &mut App) -> anyhow::Result<()> {
+        if config().aliases.contains_key(id) {
+            return Err(anyhow::anyhow!(
+                "Alias is set in {}",
+                Config::path().display()
+            ));
+        }
+        let alias = alias.trim().to_lowercase();
+        if alias.is_empty() || alias.contains(char::is_whitespace) {
+            return Err(anyhow::anyhow!("Alias must be a single word"));
+        }
+        if Self::all_aliases()
+            .iter()
+            .any(|(other, existing)| other != id && existing.eq(&alias))
+        {
+            return Err(anyhow::anyhow!("Alias already in use"));
+        }
+        CommandAliases {
+            id: id.to_string(),
+            alias,
+        }
+        .overwrite_into(&id.to_string(), db_aliases())?;
+        Self::reload(cx);
+        // Notify observers, so the root list picks up the new accessory
+        cx.update_global::<RootCommands, _>(|_, _| {});
+        Ok(())
+    }
+    pub fn unset_alias(id: &str, cx: &mut App) -> anyhow::Result<()> {
+        if config().aliases.contains_key(id) {
+            return Err(anyhow::anyhow!(
+                "Alias is set in {}",
+                Config::path().display()
+            ));
+        }
+        if let Some(alias) = CommandAliases::get(&id.to_string(), db_aliases())? {
+            alias.delete(db_aliases())?;
+        }
+        Self::reload(cx);
+        cx.update_global::<RootCommands, _>(|_, _| {});
+        Ok(())
+    }
+    // Splits `"<alias> <rest>"` into the matching command and the rest of the query
+    pub fn resolve(query: &str, cx: &App) -> Option<(RootCommand, String)> {
+        let (alias, rest) = query.split_once(' ')?;
+        let alias = alias.to_lowercase();
+        let (id, _) = cx
+            .global::<HotkeyManager>()
+            .aliases
+            .iter()
+            .find(|(_, existing)| **existing == alias)?;
+        if !Preferences::is_enabled(id) {
+            return None;
+        }
+        let command = cx.global::<RootCommands>().commands.get(id)?.clone();
+        Some((command, rest.to_string()))
+    }
+}
@@ -1,43 +1,147 @@
-use clap::Command;
-use gpui::private::serde_json;
-use smol::io::{AsyncReadExt, AsyncWriteExt};
+use std::{io::Read, path::PathBuf};
 
-use super::server::{CommandPayload, TopLevelCommand, get_command};
-use crate::{commands::RootCommands, ipc::SOCKET_PATH};
+use clap::{ArgMatches, Command};
+use serde_json::{Value, json};
+use smol::io::{AsyncBufRead, AsyncWrite, BufReader};
+
+use super::{
+    protocol::{Request, Response, read_line, write_message},
+    server::{TopLevelCommand, get_command},
+};
+
+struct Client<S> {
+    stream: S,
+    id: u64,
+}
+
+impl<S: AsyncBufRead + AsyncWrite + Unpin> Client<S> {
+    async fn request(&mut self, method: &str, params: Value) -> anyhow::Result<Value> {
+        self.id += 1;
+        write_message(&mut self.stream, &Request::new(self.id, method, params)).await?;
+        let line = read_line(&mut self.stream)
+            .await?
+            .ok_or(anyhow::anyhow!("Connection closed by server"))?;
+        let response: Response = serde_json::from_str(&line)?;
+        Ok(response.into_result()?)
+    }
+}
+
+fn archive_path(matches: &ArgMatches) -> anyhow::Result<PathBuf> {
+    let path = matches
+        .get_one::<String>("Command")
+        .ok_or(anyhow::anyhow!("Archive path missing"))?;
+    Ok(std::path::absolute(path)?)
+}
 
 pub async fn client_connect() -> anyhow::Result<()> {
     #[cfg(unix)]
-    let mut stream = {
+    let stream = {
         use smol::net::unix::UnixStream;
-        UnixStream::connect(Path::new(SOCKET_PATH)).await?
+        let path = super::socket_path();
+        UnixStream::connect(&path)
+            .await
+            .map_err(|err| anyhow::anyhow!("Failed to connect to {}: {}", path.display(), err))?
     };
 
     #[cfg(windows)]
-    let mut stream = {
+    let stream = {
         use smol::net::TcpStream;
         // Windows 上使用 TCP 替代 Unix 套接字
-        let port = SOCKET_PATH.trim_start_matches('/');
-        TcpStream::connect(format!("127.0.0.1:{}", port)).await?
+        TcpStream::connect(format!("127.0.0.1:{}", super::socket_port())).await?
+    };
+    let mut client = Client {
+        stream: BufReader::new(stream),
+        id: 0,
     };
-    let mut buf = vec![0; 8096];
-    let n = stream.read(&mut buf).await?;
-    let root_commands: RootCommands = serde_json::from_slice(&buf[..n])?;
 
-    let command: Command = get_command(&root_commands);
+    let commands = client.request("list_commands", Value::Null).await?;
+    let names: Vec<String> = commands
+        .as_array()
+        .map(|commands| {
+            commands
+                .iter()
+                .filter_map(|command| command["name"].as_str().map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let command: Command = get_command(&names);
 
     let matches = command.get_matches();
 
-    let payload: CommandPayload = CommandPayload {
-        action: matches
-            .get_one::<TopLevelCommand>("Action")
-            .ok_or(anyhow::anyhow!("Action not found"))?
-            .clone(),
-        command: matches.get_one::<String>("Command").cloned(),
-    };
+    let action = matches
+        .get_one::<TopLevelCommand>("Action")
+        .ok_or(anyhow::anyhow!("Action not found"))?
+        .clone();
+
+    // The positional argument doubles as the archive path, so command names are checked here
+    if matches!(action, TopLevelCommand::Command | TopLevelCommand::Query) {
+        if let Some(command) = matches.get_one::<String>("Command") {
+            if !names.contains(command) {
+                return Err(anyhow::anyhow!(
+                    "Unknown command {}, possible values: {}",
+                    command,
+                    names.join(", ")
+                ));
+            }
+        }
+    }
 
-    let bytes = serde_json::to_vec(&payload)?;
+    let (method, params) = match action {
+        TopLevelCommand::Toggle => ("toggle", Value::Null),
+        TopLevelCommand::Show => ("show", Value::Null),
+        TopLevelCommand::Hide => ("hide", Value::Null),
+        TopLevelCommand::Quit => ("quit", Value::Null),
+        TopLevelCommand::Command => (
+            "run_command",
+            json!({ "command": matches.get_one::<String>("Command") }),
+        ),
+        TopLevelCommand::Pipe => {
+            let delimiter = matches
+                .get_one::<String>("Delimeter")
+                .map(|d| d.replace("\\n", "\n").replace("\\t", "\t"))
+                .unwrap_or("\n".to_string());
+            let mut input = String::new();
+            std::io::stdin().read_to_string(&mut input)?;
+            let items: Vec<&str> = input
+                .split(delimiter.as_str())
+                .map(|item| item.trim_end_matches('\r'))
+                .filter(|item| !item.is_empty())
+                .collect();
+            let selected = client.request("pipe", json!({ "items": items })).await?;
+            // Raw output, so the selection can be consumed by scripts like with dmenu
+            println!("{}", selected.as_str().unwrap_or_default());
+            return Ok(());
+        }
+        TopLevelCommand::List => {
+            for name in names {
+                println!("{}", name);
+            }
+            return Ok(());
+        }
+        TopLevelCommand::State => ("get_state", Value::Null),
+        // The server resolves paths relative to its own working directory
+        TopLevelCommand::Export => (
+            "export",
+            json!({
+                "path": archive_path(&matches)?,
+                "secrets": !matches.get_flag("NoSecrets"),
+            }),
+        ),
+        TopLevelCommand::Import => ("import", json!({ "path": archive_path(&matches)? })),
+        TopLevelCommand::Query => (
+            "push_query",
+            json!({
+                "text": matches.get_one::<String>("Query"),
+                "command": matches.get_one::<String>("Command"),
+            }),
+        ),
+    };
 
-    stream.write_all(&bytes).await?;
+    let result = client.request(method, params).await?;
+    if !result.is_null() {
+        println!("{}", serde_json::to_string_pretty(&result)?);
+    }
 
     Ok(())
 }
@@ -1,5 +1,69 @@
+use std::{path::PathBuf, sync::OnceLock};
+
 pub mod client;
+mod pipe;
+pub mod protocol;
 pub mod server;
 
-pub const SOCKET_PATH: &str = "/tmp/loungy.sock";
+pub const SOCKET_NAME: &str = "loungy.sock";
+pub const SOCKET_ENV: &str = "LOUNGY_SOCKET";
+pub const SOCKET_FLAG: &str = "--socket";
 pub const SOCKET_PORT: u16 = 5555; // Windows 默认端口
+
+static SOCKET_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+// Picks up `--socket <path>` before anything else looks at the arguments, so the server and the client agree on the path
+pub fn init_socket_path(args: impl IntoIterator<Item = String>) {
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let path = if arg == SOCKET_FLAG {
+            args.next()
+        } else {
+            arg.strip_prefix(&format!("{}=", SOCKET_FLAG))
+                .map(|path| path.to_string())
+        };
+        if let Some(path) = path.filter(|path| !path.is_empty()) {
+            let _ = SOCKET_OVERRIDE.set(PathBuf::from(path));
+            return;
+        }
+    }
+}
+
+// Override flag, then `LOUNGY_SOCKET`, then the per-user runtime directory
+pub fn socket_path() -> PathBuf {
+    if let Some(path) = SOCKET_OVERRIDE.get() {
+        return path.clone();
+    }
+    if let Some(path) = std::env::var_os(SOCKET_ENV).filter(|path| !path.is_empty()) {
+        return PathBuf::from(path);
+    }
+    runtime_dir().join(SOCKET_NAME)
+}
+
+#[cfg(unix)]
+fn runtime_dir() -> PathBuf {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(fallback_runtime_dir)
+}
+
+// Only readable by the owner, created on demand by the server
+#[cfg(unix)]
+pub fn fallback_runtime_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("loungy-{}", unsafe { libc::getuid() }))
+}
+
+#[cfg(windows)]
+fn runtime_dir() -> PathBuf {
+    std::env::temp_dir()
+}
+
+// On Windows the socket is a TCP port on localhost, an override can be a plain port number
+#[cfg(windows)]
+pub fn socket_port() -> u16 {
+    let path = socket_path();
+    path.to_str()
+        .and_then(|path| path.trim_start_matches('/').parse().ok())
+        .unwrap_or(SOCKET_PORT)
+}
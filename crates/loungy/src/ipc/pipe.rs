@@ -0,0 +1,74 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+use gpui::{AnyView, App, Window};
+
+use crate::{
+    command,
+    components::{
+        list::{ItemBuilder, ListBuilder, ListItem},
+        shared::{Icon, Img},
+    },
+    state::{CommandTrait, LAction, StateViewBuilder, StateViewContext},
+    window::LWindow,
+};
+
+// dmenu style selection, the chosen entry is sent back to the `pipe` request waiting on the receiver
+#[derive(Clone)]
+pub struct PipeListBuilder {
+    pub items: Vec<String>,
+    pub sender: crossbeam_channel::Sender<String>,
+}
+command!(PipeListBuilder);
+impl StateViewBuilder for PipeListBuilder {
+    fn build(&self, context: &mut StateViewContext, window: &mut Window, cx: &mut App) -> AnyView {
+        context.query.set_placeholder("Select an entry...", cx);
+        let items = self.items.clone();
+        let sender = self.sender.clone();
+        ListBuilder::new()
+            .build(
+                move |_, _, _| {
+                    Ok(Some(
+                        items
+                            .iter()
+                            .enumerate()
+                            .map(|(i, item)| {
+                                ItemBuilder::new(
+                                    (i, item.clone()),
+                                    ListItem::new(None, item.clone(), None, vec![]),
+                                )
+                                .keywords(vec![item.clone()])
+                                .actions(vec![LAction::new(
+                                    Img::default().icon(Icon::Check),
+                                    "Select",
+                                    None,
+                                    {
+                                        let item = item.clone();
+                                        let sender = sender.clone();
+                                        move |_, cx| {
+                                            let _ = sender.try_send(item.clone());
+                                            LWindow::close(cx);
+                                        }
+                                    },
+                                    false,
+                                )])
+                                .build()
+                            })
+                            .collect(),
+                    ))
+                },
+                context,
+                window,
+                cx,
+            )
+            .into()
+    }
+}
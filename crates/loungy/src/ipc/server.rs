@@ -1,19 +1,36 @@
 use std::{
     net::{Ipv4Addr, SocketAddr},
+    path::PathBuf,
     pin::Pin,
     task::{Context, Poll},
+    time::Duration,
 };
 
-use anyhow::{Error, anyhow};
-use clap::{Arg, ValueEnum, command};
-use gpui::{AsyncApp, private::serde_json};
-use serde::{Deserialize, Serialize};
-use smol::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use anyhow::Error;
+use clap::{Arg, ArgAction, ValueEnum, command};
+use gpui::{App, AsyncApp, BorrowAppContext};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::{Value, json};
+use smol::io::{AsyncRead, AsyncWrite, BufReader};
 
-use super::{SOCKET_PATH, SOCKET_PORT};
+#[cfg(windows)]
+use super::socket_port;
+use super::{
+    SOCKET_FLAG,
+    pipe::PipeListBuilder,
+    protocol::{
+        CANCELLED, COMMAND_NOT_FOUND, INTERNAL_ERROR, INVALID_PARAMS, INVALID_REQUEST,
+        METHOD_NOT_FOUND, PARSE_ERROR, Request, Response, RpcError, VERSION, read_line,
+        write_message,
+    },
+};
+#[cfg(unix)]
+use super::{fallback_runtime_dir, socket_path};
 use crate::{
-    commands::RootCommands,
-    state::{LActions, StateModel},
+    archive::Archive,
+    commands::{RootCommand, RootCommands, reload_archived},
+    hotkey::HotkeyManager,
+    state::{CommandTrait, LActions, StateModel},
     window::LWindow,
 };
 
@@ -29,13 +46,20 @@ impl PlatformListener {
     pub async fn accept(&self) -> Result<(PlatformStream, SocketAddr), Error> {
         match self {
             #[cfg(unix)]
-            PlatformListener::Unix(listener) => {
+            PlatformListener::Unix(listener) => loop {
                 let (stream, _) = listener.accept().await?;
-                Ok((
-                    PlatformStream::Unix(stream),
-                    SocketAddr::from(([127, 0, 0, 1], 0)),
-                ))
-            }
+                // Only the user running Loungy may talk to it
+                match peer_uid(&stream) {
+                    Ok(uid) if uid == unsafe { libc::getuid() } => {
+                        return Ok((
+                            PlatformStream::Unix(stream),
+                            SocketAddr::from(([127, 0, 0, 1], 0)),
+                        ));
+                    }
+                    Ok(uid) => log::warn!("Rejected IPC connection from uid {}", uid),
+                    Err(err) => log::warn!("Failed to read IPC peer credentials: {}", err),
+                }
+            },
             #[cfg(windows)]
             PlatformListener::Tcp(listener) => {
                 let (stream, addr) = listener.accept().await?;
@@ -102,46 +126,86 @@ impl AsyncWrite for PlatformStream {
     }
 }
 
-impl PlatformStream {
-    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
-        AsyncReadExt::read(self, buf).await.map_err(Into::into)
-    }
+#[cfg(unix)]
+fn peer_uid(stream: &smol::net::unix::UnixStream) -> std::io::Result<u32> {
+    use std::os::fd::AsRawFd;
 
-    async fn write_all(&mut self, buf: &[u8]) -> Result<(), Error> {
-        AsyncWriteExt::write_all(self, buf)
-            .await
-            .map_err(Into::into)
+    let fd = stream.as_raw_fd();
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        let mut cred = libc::ucred {
+            pid: 0,
+            uid: 0,
+            gid: 0,
+        };
+        let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+        let ret = unsafe {
+            libc::getsockopt(
+                fd,
+                libc::SOL_SOCKET,
+                libc::SO_PEERCRED,
+                &mut cred as *mut libc::ucred as *mut libc::c_void,
+                &mut len,
+            )
+        };
+        if ret != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(cred.uid)
     }
-}
-
-fn extract_port_from_path(path: &str) -> Option<u16> {
-    if path.starts_with("/") {
-        path.split('/').next_back()?.parse().ok()
-    } else {
-        path.parse().ok()
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    {
+        let mut uid = 0;
+        let mut gid = 0;
+        if unsafe { libc::getpeereid(fd, &mut uid, &mut gid) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(uid)
     }
 }
+
 pub async fn setup_socket() -> anyhow::Result<PlatformListener> {
     #[cfg(unix)]
     {
         use smol::net::unix::{UnixListener, UnixStream};
+        use std::{
+            fs::{self, DirBuilder, Permissions},
+            os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt},
+        };
+
+        let path = socket_path();
+        if let Some(dir) = path.parent().filter(|dir| !dir.exists()) {
+            DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+        }
+        // The fallback lives in the shared temp dir, so make sure nobody else planted it
+        let fallback = fallback_runtime_dir();
+        if path.parent() == Some(fallback.as_path()) {
+            let metadata = fs::metadata(&fallback)?;
+            if metadata.uid() != unsafe { libc::getuid() } || metadata.mode() & 0o077 != 0 {
+                return Err(anyhow::anyhow!(
+                    "Insecure socket directory: {}",
+                    fallback.display()
+                ));
+            }
+        }
 
-        if Path::new(SOCKET_PATH).exists() {
-            if UnixStream::connect(Path::new(SOCKET_PATH)).await.is_ok() {
+        if path.exists() {
+            if UnixStream::connect(&path).await.is_ok() {
                 return Err(anyhow::anyhow!("Server already running"));
             }
-            std::fs::remove_file(SOCKET_PATH)?;
+            fs::remove_file(&path)?;
         };
 
-        let listener = UnixListener::bind(Path::new(SOCKET_PATH))?;
-        log::info!("Listening on Unix socket: {}", SOCKET_PATH);
+        let listener = UnixListener::bind(&path)?;
+        fs::set_permissions(&path, Permissions::from_mode(0o600))?;
+        log::info!("Listening on Unix socket: {}", path.display());
 
         Ok(PlatformListener::Unix(listener))
     }
 
     #[cfg(windows)]
     {
-        let port = extract_port_from_path(SOCKET_PATH).unwrap_or(SOCKET_PORT);
+        let port = socket_port();
         let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
 
         // 检查端口是否被占用
@@ -156,14 +220,12 @@ pub async fn setup_socket() -> anyhow::Result<PlatformListener> {
     }
 }
 pub async fn start_server(listener: PlatformListener, cx: AsyncApp) -> anyhow::Result<()> {
-    let commands = cx.read_global::<RootCommands, _>(|commands, _| commands.clone())?;
     loop {
         let (stream, _) = listener.accept().await?;
         let cx = cx.clone();
-        let commands = commands.clone();
 
         cx.spawn(async move |cx| {
-            if let Err(e) = handle_client(stream, commands, cx).await {
+            if let Err(e) = handle_client(stream, cx).await {
                 log::error!("Client handling error: {}", e);
             }
         })
@@ -171,75 +233,300 @@ pub async fn start_server(listener: PlatformListener, cx: AsyncApp) -> anyhow::R
     }
 }
 
-async fn handle_client(
-    mut stream: PlatformStream,
-    commands: RootCommands,
-    cx: &mut AsyncApp,
-) -> anyhow::Result<()> {
-    // Send available commands to the client
-    let bytes = serde_json::to_vec(&commands)?;
-    stream.write_all(&bytes).await?;
+// A client can send any number of requests over one connection, each one gets exactly one response
+async fn handle_client(stream: PlatformStream, cx: &mut AsyncApp) -> anyhow::Result<()> {
+    let mut stream = BufReader::new(stream);
+    while let Some(line) = read_line(&mut stream).await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(request) if request.jsonrpc != VERSION => Response::new(
+                request.id,
+                Err(RpcError::new(
+                    INVALID_REQUEST,
+                    "Unsupported jsonrpc version",
+                )),
+            ),
+            Ok(request) => {
+                let id = request.id.clone();
+                Response::new(id, dispatch(request, cx).await)
+            }
+            Err(err) => Response::new(
+                Value::Null,
+                Err(RpcError::new(PARSE_ERROR, format!("Parse error: {}", err))),
+            ),
+        };
+        write_message(stream.get_mut(), &response).await?;
+    }
+    Ok(())
+}
 
-    let mut buf = vec![0; 1024];
-    let n = stream.read(&mut buf).await?;
+fn params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(RpcError::invalid_params)
+}
 
-    let matches: CommandPayload = serde_json::from_slice(&buf[..n])?;
+fn update(
+    cx: &mut AsyncApp,
+    f: impl FnOnce(&mut App) -> Result<Value, RpcError>,
+) -> Result<Value, RpcError> {
+    cx.update(f)
+        .map_err(|err| RpcError::new(INTERNAL_ERROR, err))?
+}
 
-    let _ = cx.update::<anyhow::Result<()>>(|cx| {
-        match matches.action {
-            TopLevelCommand::Toggle => {
-                LWindow::toggle(cx);
-            }
-            TopLevelCommand::Show => {
-                LWindow::open(cx);
-            }
-            TopLevelCommand::Hide => {
-                LWindow::close(cx);
-            }
-            TopLevelCommand::Quit => {
-                cx.quit();
-            }
-            TopLevelCommand::Command => {
-                let Some(c) = matches.command else {
-                    return Err(anyhow!("No command provided"));
-                };
-                let Some((_, command)) = commands.commands.iter().find(|(k, _)| {
-                    let split = k.split("::").collect::<Vec<_>>();
-                    c.eq(split[2])
-                }) else {
-                    return Err(anyhow!("Command not found"));
+async fn dispatch(request: Request, cx: &mut AsyncApp) -> Result<Value, RpcError> {
+    match request.method.as_str() {
+        "toggle" => update(cx, move |cx| {
+            LWindow::toggle(cx);
+            Ok(Value::Null)
+        }),
+        "show" => update(cx, move |cx| {
+            LWindow::open(cx);
+            Ok(Value::Null)
+        }),
+        "hide" => update(cx, move |cx| {
+            LWindow::close(cx);
+            Ok(Value::Null)
+        }),
+        "quit" => update(cx, move |cx| {
+            cx.quit();
+            Ok(Value::Null)
+        }),
+        "list_commands" => update(cx, move |cx| {
+            let commands = cx.global::<RootCommands>();
+            let mut list = commands
+                .commands
+                .values()
+                .map(|command| {
+                    let mut value = serde_json::to_value(command).unwrap_or_default();
+                    value["name"] = Value::from(command_name(&command.id));
+                    value
+                })
+                .collect::<Vec<_>>();
+            list.sort_by_key(|value| value["name"].as_str().unwrap_or_default().to_string());
+            Ok(Value::from(list))
+        }),
+        "run_command" => {
+            let RunCommandParams { command } = params(request.params)?;
+            update(cx, move |cx| {
+                run_command(&command, cx)?;
+                Ok(Value::Null)
+            })
+        }
+        "push_query" => {
+            let PushQueryParams { text, command } = params(request.params)?;
+            update(cx, move |cx| {
+                if let Some(command) = &command {
+                    run_command(command, cx)?;
+                }
+                let Some(query) = cx
+                    .global::<StateModel>()
+                    .inner
+                    .read(cx)
+                    .stack
+                    .last()
+                    .map(|item| item.query.downgrade())
+                else {
+                    return Err(RpcError::new(INTERNAL_ERROR, "No active view"));
                 };
+                query.set_text(text, cx);
+                LWindow::open(cx);
+                Ok(Value::Null)
+            })
+        }
+        "get_state" => {
+            let open = LWindow::is_open(cx);
+            update(cx, move |cx| {
+                let state = cx.global::<StateModel>().inner.read(cx);
+                let stack = state
+                    .stack
+                    .iter()
+                    .map(|item| item.id.to_string())
+                    .collect::<Vec<_>>();
+                let query = state
+                    .stack
+                    .last()
+                    .map(|item| item.query.downgrade().get_text(cx))
+                    .unwrap_or_default();
+                Ok(json!({
+                    "open": open,
+                    "stack": stack,
+                    "query": query,
+                }))
+            })
+        }
+        "pipe" => {
+            let PipeParams { items } = params(request.params)?;
+            pipe(items, cx).await
+        }
+        "export" => {
+            let ExportParams { path, secrets } = params(request.params)?;
+            let documents = Archive::export(&path, secrets)
+                .map_err(|err| RpcError::new(INTERNAL_ERROR, err))?;
+            Ok(json!({ "documents": documents }))
+        }
+        "import" => {
+            let ImportParams { path } = params(request.params)?;
+            let documents =
+                Archive::import(&path).map_err(|err| RpcError::new(INTERNAL_ERROR, err))?;
+            update(cx, move |cx| {
+                // Reload whatever keeps the imported data in memory
+                reload_archived(cx);
+                // Re-renders the root list for the imported aliases and hotkeys
+                cx.update_global::<RootCommands, _>(|_, _| {});
+                HotkeyManager::reload_toggle(cx);
+                HotkeyManager::update(cx);
+                cx.refresh();
+                Ok(json!({ "documents": documents }))
+            })
+        }
+        method => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("Method not found: {}", method),
+        )),
+    }
+}
 
-                let state = cx.global::<StateModel>();
-                let state = state.inner.read(cx);
-                let mut is_active = false;
-                if let Some(active) = state.stack.last() {
-                    is_active = active.id.eq(&command.id);
-                };
-                if !is_active {
-                    StateModel::update(
-                        |this, cx| {
-                            this.reset(cx);
-                        },
-                        cx,
-                    );
-                    (command.action)(&mut LActions::default(cx), cx);
-                    LWindow::open(cx);
-                } else {
-                    LWindow::toggle(cx);
-                }
+// Command ids are module paths like `loungy::commands::theme::list`, the CLI addresses them by the third segment
+pub fn command_name(id: &str) -> String {
+    id.split("::").nth(2).unwrap_or(id).to_string()
+}
+
+fn run_command(name: &str, cx: &mut App) -> Result<(), RpcError> {
+    let commands = cx.global::<RootCommands>();
+    // A full id always wins, a short name has to be unique
+    let command = match commands.commands.get(name) {
+        Some(command) => command.clone(),
+        None => {
+            let mut matches: Vec<&RootCommand> = commands
+                .commands
+                .values()
+                .filter(|command| command_name(&command.id).eq(name))
+                .collect();
+            if matches.len() > 1 {
+                let mut ids: Vec<&str> =
+                    matches.iter().map(|command| command.id.as_str()).collect();
+                ids.sort();
+                return Err(RpcError::new(
+                    INVALID_PARAMS,
+                    format!(
+                        "Ambiguous command name: {}, use one of {}",
+                        name,
+                        ids.join(", ")
+                    ),
+                ));
             }
-            TopLevelCommand::Pipe => {}
+            let Some(command) = matches.pop().cloned() else {
+                return Err(RpcError::new(
+                    COMMAND_NOT_FOUND,
+                    format!("Command not found: {}", name),
+                ));
+            };
+            command
         }
-        Ok(())
-    });
+    };
+
+    let state = cx.global::<StateModel>();
+    let state = state.inner.read(cx);
+    let mut is_active = false;
+    if let Some(active) = state.stack.last() {
+        is_active = active.id.eq(&command.id);
+    };
+    if !is_active {
+        StateModel::update(
+            |this, cx| {
+                this.reset(cx);
+            },
+            cx,
+        );
+        (command.action)(&mut LActions::default(cx), cx);
+        LWindow::open(cx);
+    } else {
+        LWindow::toggle(cx);
+    }
     Ok(())
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct CommandPayload {
-    pub action: TopLevelCommand,
-    pub command: Option<String>,
+// Shows the items as a list and resolves once one gets selected, or fails if the view is dismissed
+async fn pipe(items: Vec<String>, cx: &mut AsyncApp) -> Result<Value, RpcError> {
+    let (sender, receiver) = crossbeam_channel::bounded::<String>(1);
+    let builder = PipeListBuilder { items, sender };
+    let id = builder.command();
+    update(cx, move |cx| {
+        let Some(handle) = cx.windows().first().copied() else {
+            return Err(RpcError::new(INTERNAL_ERROR, "No window available"));
+        };
+        cx.update_window(handle, |_, window, cx| {
+            StateModel::update(
+                |this, cx| {
+                    this.reset(cx);
+                    this.push(builder, window, cx);
+                },
+                cx,
+            );
+        })
+        .map_err(|err| RpcError::new(INTERNAL_ERROR, err))?;
+        LWindow::open(cx);
+        Ok(Value::Null)
+    })?;
+
+    loop {
+        if let Ok(selected) = receiver.try_recv() {
+            return Ok(Value::from(selected));
+        }
+        let open = LWindow::is_open(cx);
+        let active = cx
+            .update(|cx| {
+                cx.global::<StateModel>()
+                    .inner
+                    .read(cx)
+                    .stack
+                    .last()
+                    .is_some_and(|item| item.id.eq(&id))
+            })
+            .unwrap_or_default();
+        if !open || !active {
+            let _ = cx.update(|cx| {
+                StateModel::update(|this, cx| this.reset(cx), cx);
+            });
+            return Err(RpcError::new(CANCELLED, "Selection cancelled"));
+        }
+        cx.background_executor()
+            .timer(Duration::from_millis(50))
+            .await;
+    }
+}
+
+#[derive(Deserialize)]
+struct PipeParams {
+    items: Vec<String>,
+}
+
+fn default_secrets() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+struct ExportParams {
+    path: PathBuf,
+    #[serde(default = "default_secrets")]
+    secrets: bool,
+}
+
+#[derive(Deserialize)]
+struct ImportParams {
+    path: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct RunCommandParams {
+    command: String,
+}
+
+#[derive(Deserialize)]
+struct PushQueryParams {
+    text: String,
+    command: Option<String>,
 }
 
 #[derive(Clone, Debug, ValueEnum, Serialize, Deserialize)]
@@ -250,6 +537,11 @@ pub enum TopLevelCommand {
     Quit,
     Command,
     Pipe,
+    List,
+    State,
+    Query,
+    Export,
+    Import,
 }
 
 impl From<TopLevelCommand> for clap::builder::OsStr {
@@ -261,11 +553,16 @@ impl From<TopLevelCommand> for clap::builder::OsStr {
             TopLevelCommand::Quit => "quit".into(),
             TopLevelCommand::Command => "command".into(),
             TopLevelCommand::Pipe => "pipe".into(),
+            TopLevelCommand::List => "list".into(),
+            TopLevelCommand::State => "state".into(),
+            TopLevelCommand::Query => "query".into(),
+            TopLevelCommand::Export => "export".into(),
+            TopLevelCommand::Import => "import".into(),
         }
     }
 }
 
-pub fn get_command(commands: &RootCommands) -> clap::Command {
+pub fn get_command(commands: &[String]) -> clap::Command {
     command!()
         .arg(
             Arg::new("Action")
@@ -274,23 +571,40 @@ pub fn get_command(commands: &RootCommands) -> clap::Command {
         )
         .arg(
             Arg::new("Command")
-                .required_if_eq("Action", TopLevelCommand::Command)
-                .value_parser(
-                    commands
-                        .commands
-                        .keys()
-                        .map(|key| {
-                            let split = key.split("::").collect::<Vec<_>>();
-                            split[2].to_string()
-                        })
-                        .collect::<Vec<_>>(),
-                ),
+                .value_name("COMMAND|FILE")
+                .help(format!(
+                    "Command to open, one of: {}. The archive for export and import",
+                    commands.join(", ")
+                ))
+                .required_if_eq_any([
+                    ("Action", TopLevelCommand::Command),
+                    ("Action", TopLevelCommand::Export),
+                    ("Action", TopLevelCommand::Import),
+                ]),
+        )
+        .arg(
+            Arg::new("Query")
+                .long("query")
+                .short('q')
+                .required_if_eq("Action", TopLevelCommand::Query),
         )
         .arg(
             Arg::new("Delimeter")
                 .long("Delimeter")
                 .short('d')
-                .required_if_eq("Action", TopLevelCommand::Pipe)
-                .default_value(" "),
+                .help("Separates the entries read from stdin when piping, supports \\n and \\t")
+                .default_value("\\n"),
+        )
+        .arg(
+            Arg::new("NoSecrets")
+                .long("no-secrets")
+                .action(ArgAction::SetTrue)
+                .help("Leave Bitwarden client secrets, passwords and sessions as well as Matrix sessions out of the export"),
+        )
+        .arg(
+            Arg::new("Socket")
+                .long(SOCKET_FLAG.trim_start_matches('-'))
+                .global(true)
+                .help("Socket of the instance to talk to, defaults to $LOUNGY_SOCKET or $XDG_RUNTIME_DIR/loungy.sock"),
         )
 }
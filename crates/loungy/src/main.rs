@@ -19,8 +19,10 @@ use gpui::Application;
 use ipc::{client::client_connect, server::setup_socket};
 
 mod app;
+mod archive;
 mod commands;
 mod components;
+mod config;
 mod date;
 mod db;
 mod hotkey;
@@ -37,11 +39,14 @@ mod workspace;
 #[async_std::main]
 async fn main() {
     env_logger::init();
+    ipc::init_socket_path(std::env::args().skip(1));
 
     if let Ok(listener) = setup_socket().await {
         let app = Application::new();
         run_app(listener, app);
     } else if let Err(e) = client_connect().await {
         log::error!("CLI Error: {:?}", e);
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
     }
 }
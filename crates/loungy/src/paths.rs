@@ -9,10 +9,12 @@
  *
  */
 
-use std::{path::PathBuf, sync::OnceLock};
+use std::{env, path::PathBuf, sync::OnceLock};
 
 pub struct LPaths {
-    pub path_env: String,
+    // Resolved on first use, since asking the login shell can take a while
+    path_env: OnceLock<String>,
+    pub home: PathBuf,
     pub cache: PathBuf,
     pub config: PathBuf,
     pub data: PathBuf,
@@ -20,53 +22,149 @@ pub struct LPaths {
 
 pub static NAME: &str = "loungy";
 
+// Points every Loungy folder somewhere else, for portable installs or throwaway test profiles
+pub static HOME_OVERRIDE: &str = "LOUNGY_HOME";
+
+// How long the login shell gets to report its `PATH`
+#[cfg(not(target_os = "windows"))]
+const SHELL_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+// An absolute path from the environment, relative ones are ignored as the XDG spec asks
+pub(crate) fn env_dir(key: &str) -> Option<PathBuf> {
+    env::var_os(key)
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+}
+
+fn home_dir() -> PathBuf {
+    #[cfg(not(target_os = "windows"))]
+    let home = env_dir("HOME");
+    #[cfg(target_os = "windows")]
+    let home = env_dir("USERPROFILE");
+    home.unwrap_or_else(|| {
+        let username = whoami::username();
+        #[cfg(target_os = "macos")]
+        let home = PathBuf::from("/Users").join(username);
+        #[cfg(target_os = "linux")]
+        let home = PathBuf::from("/home").join(username);
+        #[cfg(target_os = "windows")]
+        let home = PathBuf::from("C:\\Users").join(username);
+        home
+    })
+}
+
+// Apps started from a desktop session don't inherit the `PATH` set up in shell profiles,
+// so ask the login shell for it and keep the inherited entries after it
+#[cfg(not(target_os = "windows"))]
+fn path_env() -> String {
+    let mut dirs: Vec<PathBuf> = login_shell_path()
+        .map(|path| env::split_paths(&path).collect())
+        .unwrap_or_default();
+    if let Some(path) = env::var_os("PATH") {
+        for dir in env::split_paths(&path) {
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+    }
+    dirs.retain(|dir| !dir.as_os_str().is_empty());
+    env::join_paths(dirs)
+        .map(|path| path.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(target_os = "windows")]
+fn path_env() -> String {
+    env::var("PATH").unwrap_or_default()
+}
+
+#[cfg(not(target_os = "windows"))]
+fn login_shell_path() -> Option<String> {
+    use log::error;
+    use std::{
+        io::Read,
+        process::{Command, Stdio},
+        sync::mpsc,
+    };
+
+    let shell = env::var("SHELL")
+        .ok()
+        .filter(|shell| !shell.is_empty())
+        .unwrap_or_else(|| "/bin/sh".to_string());
+    let mut child = Command::new(&shell)
+        .args(["-l", "-c", "printf '%s' \"$PATH\""])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .inspect_err(|err| error!("Failed to start login shell {}: {}", shell, err))
+        .ok()?;
+    // Read while the shell runs, so chatty profiles can't fill up the pipe and stall it
+    let mut stdout = child.stdout.take()?;
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut output = vec![];
+        let _ = tx.send(stdout.read_to_end(&mut output).map(|_| output));
+    });
+    // Profiles that wait for input would otherwise never finish
+    let output = match rx.recv_timeout(SHELL_TIMEOUT) {
+        Ok(output) => output.ok(),
+        Err(_) => {
+            error!("Login shell {} didn't report its PATH in time", shell);
+            let _ = child.kill();
+            None
+        }
+    };
+    let _ = child.wait();
+    let path = String::from_utf8(output?).ok()?;
+    // Profiles may print banners, the PATH is whatever comes last
+    let path = path.lines().last()?.trim().to_string();
+    (!path.is_empty()).then_some(path)
+}
+
 impl LPaths {
     pub fn new() -> Self {
-        let username = whoami::username();
+        let home = home_dir();
+        if let Some(root) = env_dir(HOME_OVERRIDE) {
+            return Self {
+                path_env: OnceLock::new(),
+                home,
+                cache: root.join("cache"),
+                config: root.join("config"),
+                data: root.join("data"),
+            };
+        }
+
         #[cfg(target_os = "macos")]
-        let user_dir = PathBuf::from("/Users").join(username.clone());
+        let (cache, config, data) = (
+            home.join("Library/Caches"),
+            home.join(".config"),
+            home.join("Library/Application Support"),
+        );
         #[cfg(target_os = "linux")]
-        let user_dir = PathBuf::from("/home").join(username.clone());
+        let (cache, config, data) = (
+            home.join(".cache"),
+            home.join(".config"),
+            home.join(".local/share"),
+        );
         #[cfg(target_os = "windows")]
-        let user_dir = PathBuf::from("C:\\Users").join(username.clone());
-        let user_dir_str = user_dir.to_string_lossy().to_string();
+        let (cache, config, data) = (
+            home.join(".cache"),
+            home.join(".config"),
+            home.join("Library/Application Support"),
+        );
         Self {
-            #[cfg(target_os = "macos")]
-            path_env: format!(
-                "/opt/homebrew/bin:/usr/local/bin:/Users/{}/.nix-profile/bin",
-                username
-            ),
-            #[cfg(target_os = "linux")]
-            path_env: format!(
-                "/opt/homebrew/bin:/usr/local/bin:/home/{}/.nix-profile/bin",
-                username
-            ),
-            #[cfg(target_os = "windows")]
-            path_env: format!(
-                "C:\\Windows\\System32;C:\\Windows;{}\\.cargo\\bin;{}\\.local\\bin",
-                user_dir_str, user_dir_str
-            ),
-            #[cfg(target_os = "macos")]
-            cache: user_dir.clone().join("Library/Caches").join(NAME),
-            #[cfg(target_os = "linux")]
-            cache: user_dir.clone().join(".cache").join(NAME),
-            #[cfg(target_os = "windows")]
-            cache: user_dir.clone().join(".cache").join(NAME),
-            config: user_dir.clone().join(".config").join(NAME),
-            #[cfg(target_os = "macos")]
-            data: user_dir
-                .clone()
-                .join("Library/Application Support")
-                .join(NAME),
-            #[cfg(target_os = "linux")]
-            data: user_dir.clone().join(".local/share").join(NAME),
-            #[cfg(target_os = "windows")]
-            data: user_dir
-                .clone()
-                .join("Library/Application Support")
-                .join(NAME),
+            path_env: OnceLock::new(),
+            cache: env_dir("XDG_CACHE_HOME").unwrap_or(cache).join(NAME),
+            config: env_dir("XDG_CONFIG_HOME").unwrap_or(config).join(NAME),
+            data: env_dir("XDG_DATA_HOME").unwrap_or(data).join(NAME),
+            home,
         }
     }
+    // `PATH` for the tools Loungy runs
+    pub fn path_env(&self) -> &str {
+        self.path_env.get_or_init(path_env)
+    }
 }
 
 pub fn paths() -> &'static LPaths {
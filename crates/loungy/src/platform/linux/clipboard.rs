@@ -0,0 +1,366 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+use std::{
+    env,
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use gpui::App;
+use url::Url;
+
+use crate::{paths::paths, platform::ClipboardWatcher, window::LWindow};
+
+#[derive(Clone, Copy, PartialEq)]
+pub(super) enum Session {
+    Wayland,
+    X11,
+}
+
+impl Session {
+    pub(super) fn detect() -> Self {
+        if env::var_os("WAYLAND_DISPLAY").is_some() {
+            Session::Wayland
+        } else {
+            Session::X11
+        }
+    }
+}
+
+// Writes `data` to the clipboard selection using `wl-copy` or `xclip`.
+// Both tools fork into the background to keep serving the selection after we return.
+pub(super) fn set_clipboard(mime: &str, data: &[u8]) -> anyhow::Result<()> {
+    let mut command = match Session::detect() {
+        Session::Wayland => {
+            let mut command = Command::new("wl-copy");
+            command.arg("--type").arg(mime);
+            command
+        }
+        Session::X11 => {
+            let mut command = Command::new("xclip");
+            command.args(["-selection", "clipboard", "-t", mime]);
+            command
+        }
+    };
+    let mut child = command
+        .env("PATH", paths().path_env())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .ok_or(anyhow!("Failed to open clipboard stdin"))?
+        .write_all(data)?;
+    child.wait()?;
+    Ok(())
+}
+
+// Simulates the paste shortcut in the focused application.
+fn send_paste() -> anyhow::Result<()> {
+    let status = match Session::detect() {
+        Session::Wayland => Command::new("wtype")
+            .args(["-M", "ctrl", "v", "-m", "ctrl"])
+            .env("PATH", paths().path_env())
+            .status()?,
+        Session::X11 => Command::new("xdotool")
+            .args(["key", "--clearmodifiers", "ctrl+v"])
+            .env("PATH", paths().path_env())
+            .status()?,
+    };
+    if !status.success() {
+        return Err(anyhow!("Paste keystroke exited with {}", status));
+    }
+    Ok(())
+}
+
+// Moves the caret of the focused application back by `count` characters
+pub fn move_cursor_left(count: usize) -> anyhow::Result<()> {
+    if count == 0 {
+        return Ok(());
+    }
+    let status = match Session::detect() {
+        Session::Wayland => Command::new("wtype")
+            .args(std::iter::repeat_n(["-k", "Left"], count).flatten())
+            .env("PATH", paths().path_env())
+            .status()?,
+        Session::X11 => Command::new("xdotool")
+            .args(["key", "--clearmodifiers", "--repeat"])
+            .arg(count.to_string())
+            .arg("Left")
+            .env("PATH", paths().path_env())
+            .status()?,
+    };
+    if !status.success() {
+        return Err(anyhow!("Cursor keystrokes exited with {}", status));
+    }
+    Ok(())
+}
+
+fn paste(mime: String, data: Vec<u8>, cx: &mut App) {
+    LWindow::close(cx);
+    cx.spawn(async move |cx| {
+        while LWindow::is_open(cx) {
+            cx.background_executor()
+                .timer(Duration::from_millis(10))
+                .await;
+        }
+        // Give the window manager a moment to hand focus back to the previous application
+        cx.background_executor()
+            .timer(Duration::from_millis(100))
+            .await;
+        ClipboardWatcher::disabled(cx);
+        if let Err(err) = set_clipboard(&mime, &data).and_then(|_| send_paste()) {
+            log::error!("Failed to paste: {:?}", err);
+        }
+    })
+    .detach();
+}
+
+pub fn close_and_paste(value: &str, _formatting: bool, cx: &mut App) {
+    paste(
+        "text/plain;charset=utf-8".to_string(),
+        value.as_bytes().to_vec(),
+        cx,
+    );
+}
+
+pub fn close_and_paste_file(path: &Path, cx: &mut App) {
+    // Images are pasted as image data, everything else as a file reference
+    let is_png = path
+        .extension()
+        .map(|ext| ext.eq_ignore_ascii_case("png"))
+        .unwrap_or(false);
+    if is_png {
+        if let Ok(bytes) = std::fs::read(path) {
+            paste("image/png".to_string(), bytes, cx);
+            return;
+        }
+    }
+    let Ok(url) = Url::from_file_path(path) else {
+        log::error!("Failed to build file url for {:?}", path);
+        return;
+    };
+    paste(
+        "text/uri-list".to_string(),
+        format!("{}\r\n", url).into_bytes(),
+        cx,
+    );
+}
+
+// Recognizes the text in an image using tesseract and copies it to the clipboard
+pub fn ocr(path: &Path) {
+    let output = Command::new("tesseract")
+        .arg(path)
+        .arg("stdout")
+        .env("PATH", paths().path_env())
+        .stderr(Stdio::null())
+        .output();
+    match output {
+        Ok(output) if output.status.success() => {
+            let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            if let Err(err) = set_clipboard("text/plain;charset=utf-8", text.as_bytes()) {
+                log::error!("Failed to copy recognized text: {:?}", err);
+            }
+        }
+        Ok(output) => log::error!("tesseract exited with {}", output.status),
+        Err(err) => log::error!("Failed to run tesseract: {:?}", err),
+    }
+}
+
+// Formats currently offered on the clipboard, password managers mark their entries with extra ones
+#[cfg(feature = "clipboard")]
+pub fn clipboard_types() -> Vec<String> {
+    let mut command = match Session::detect() {
+        Session::Wayland => {
+            let mut command = Command::new("wl-paste");
+            command.arg("--list-types");
+            command
+        }
+        Session::X11 => {
+            let mut command = Command::new("xclip");
+            command.args(["-selection", "clipboard", "-t", "TARGETS", "-o"]);
+            command
+        }
+    };
+    command
+        .env("PATH", paths().path_env())
+        .stderr(Stdio::null())
+        .output()
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .map(|line| line.trim().to_string())
+                .filter(|line| !line.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Tells when the selection might have changed, so its contents are only read and hashed then
+#[cfg(feature = "clipboard")]
+enum ChangeSignal {
+    // `wl-paste --watch` runs its command for every new selection
+    Watch(std::sync::mpsc::Receiver<()>, std::process::Child),
+    // When the current owner acquired the X11 selection
+    Timestamp(Option<String>),
+    Poll,
+}
+
+#[cfg(feature = "clipboard")]
+impl ChangeSignal {
+    fn new() -> Self {
+        match Session::detect() {
+            Session::Wayland => Self::watch().unwrap_or_else(|err| {
+                log::error!("Failed to watch clipboard, polling instead: {:?}", err);
+                Self::Poll
+            }),
+            Session::X11 => Self::Timestamp(None),
+        }
+    }
+    fn watch() -> anyhow::Result<Self> {
+        use std::io::{BufRead, BufReader};
+
+        let mut child = Command::new("wl-paste")
+            .args(["--watch", "echo"])
+            .env("PATH", paths().path_env())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or(anyhow!("Failed to open wl-paste stdout"))?;
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                if line.is_err() || tx.send(()).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Self::Watch(rx, child))
+    }
+    fn changed(&mut self) -> bool {
+        use std::sync::mpsc::TryRecvError;
+
+        match self {
+            Self::Watch(rx, _) => {
+                let mut changed = false;
+                loop {
+                    match rx.try_recv() {
+                        Ok(()) => changed = true,
+                        Err(TryRecvError::Empty) => return changed,
+                        Err(TryRecvError::Disconnected) => {
+                            log::error!("Clipboard watcher exited, polling instead");
+                            *self = Self::Poll;
+                            return true;
+                        }
+                    }
+                }
+            }
+            Self::Timestamp(last) => {
+                let output = Command::new("xclip")
+                    .args(["-selection", "clipboard", "-t", "TIMESTAMP", "-o"])
+                    .env("PATH", paths().path_env())
+                    .stderr(Stdio::null())
+                    .output();
+                let stamp = match output {
+                    Ok(output) if output.status.success() => {
+                        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+                    }
+                    Ok(_) => None,
+                    Err(err) => {
+                        log::error!("Failed to run xclip, polling instead: {:?}", err);
+                        *self = Self::Poll;
+                        return true;
+                    }
+                };
+                // Owners that don't answer with a timestamp are read every time
+                let changed = stamp.is_none() || *last != stamp;
+                *last = stamp;
+                changed
+            }
+            Self::Poll => true,
+        }
+    }
+}
+
+#[cfg(feature = "clipboard")]
+impl Drop for ChangeSignal {
+    fn drop(&mut self) {
+        if let Self::Watch(_, child) = self {
+            let _ = child.kill();
+        }
+    }
+}
+
+#[cfg(feature = "clipboard")]
+pub async fn clipboard(mut on_change: impl FnMut(&mut gpui::AsyncApp), mut cx: gpui::AsyncApp) {
+    use std::{
+        collections::hash_map::DefaultHasher,
+        hash::{Hash, Hasher},
+    };
+
+    // There is no change counter for X11/Wayland selections, so the contents are compared once they might have changed
+    let mut cp = match arboard::Clipboard::new() {
+        Ok(cp) => cp,
+        Err(err) => {
+            log::error!("Failed to open clipboard: {:?}", err);
+            return;
+        }
+    };
+    let mut signal = ChangeSignal::new();
+    let mut hash: u64 = 0;
+    let mut first = true;
+    loop {
+        // Asking the tools might take a moment, which shouldn't hold up the UI
+        let changed;
+        (signal, changed) = cx
+            .background_executor()
+            .spawn(async move {
+                let changed = signal.changed();
+                (signal, changed)
+            })
+            .await;
+        if changed || first {
+            first = false;
+            let mut hasher = DefaultHasher::new();
+            let read = if let Ok(text) = cp.get_text() {
+                text.hash(&mut hasher);
+                true
+            } else if let Ok(image) = cp.get_image() {
+                image.bytes.hash(&mut hasher);
+                true
+            } else {
+                false
+            };
+            if read {
+                let new_hash = hasher.finish();
+                if new_hash != hash {
+                    // Skip the initial clipboard contents on startup
+                    if hash != 0 {
+                        on_change(&mut cx);
+                    }
+                    hash = new_hash;
+                }
+            }
+        }
+        cx.background_executor()
+            .timer(Duration::from_millis(250))
+            .await;
+    }
+}
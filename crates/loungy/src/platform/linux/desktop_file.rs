@@ -9,65 +9,553 @@
  *
  */
 
+// Parsing and launching of desktop entries, see https://specifications.freedesktop.org/desktop-entry-spec/latest/
+
 use freedesktop_entry_parser::{AttrSelector, parse_entry};
 use freedesktop_icons::lookup;
-use std::path::PathBuf;
+use std::{
+    env, fmt,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::OnceLock,
+};
+
+use crate::paths::paths;
+
+// Terminals tried in order when `$TERMINAL` isn't set, with the flag that precedes the command
+const TERMINALS: [(&str, Option<&str>); 7] = [
+    ("x-terminal-emulator", Some("-e")),
+    ("gnome-terminal", Some("--")),
+    ("konsole", Some("-e")),
+    ("xfce4-terminal", Some("-x")),
+    ("alacritty", Some("-e")),
+    ("foot", None),
+    ("xterm", Some("-e")),
+];
+
+pub(crate) struct DesktopAction {
+    pub id: String,
+    pub name: String,
+    pub icon: Option<String>,
+    pub exec: String,
+}
 
 pub(crate) struct ApplicationDesktopFile {
+    pub path: PathBuf,
     pub name: String,
+    pub generic_name: Option<String>,
+    pub comment: Option<String>,
     pub icon: Option<String>,
     pub keywords: Vec<String>,
+    pub exec: String,
+    pub working_dir: Option<PathBuf>,
+    pub terminal: bool,
+    pub actions: Vec<DesktopAction>,
 }
 
+#[derive(Debug)]
 pub(crate) enum DesktopFileError {
     FileNotFound,
     NoDesktopEntry,
     InvalidFormat,
     HiddenFile,
+    NotInstalled,
+}
+
+impl fmt::Display for DesktopFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            Self::FileNotFound => "Desktop file not found",
+            Self::NoDesktopEntry => "Missing desktop entry",
+            Self::InvalidFormat => "Invalid desktop file",
+            Self::HiddenFile => "Application is hidden",
+            Self::NotInstalled => "Application is not installed",
+        };
+        f.write_str(message)
+    }
+}
+
+impl std::error::Error for DesktopFileError {}
+
+// Locale variants to look up localized keys with, most specific first
+fn locales() -> &'static [String] {
+    static LOCALES: OnceLock<Vec<String>> = OnceLock::new();
+    LOCALES.get_or_init(|| {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|key| env::var(key).ok())
+            .find(|value| !value.is_empty())
+            .map(|locale| locale_variants(&locale))
+            .unwrap_or_default()
+    })
+}
+
+// lang_COUNTRY.ENCODING@MODIFIER, the encoding is ignored
+fn locale_variants(locale: &str) -> Vec<String> {
+    let (locale, modifier) = match locale.split_once('@') {
+        Some((locale, modifier)) => (locale, Some(modifier)),
+        None => (locale, None),
+    };
+    let locale = locale.split('.').next().unwrap_or_default();
+    if locale.is_empty() || locale == "C" || locale == "POSIX" {
+        return vec![];
+    }
+    let (lang, country) = match locale.split_once('_') {
+        Some((lang, country)) => (lang, Some(country)),
+        None => (locale, None),
+    };
+    let mut locales = vec![];
+    if let (Some(country), Some(modifier)) = (country, modifier) {
+        locales.push(format!("{}_{}@{}", lang, country, modifier));
+    }
+    if let Some(country) = country {
+        locales.push(format!("{}_{}", lang, country));
+    }
+    if let Some(modifier) = modifier {
+        locales.push(format!("{}@{}", lang, modifier));
+    }
+    locales.push(lang.to_string());
+    locales
+}
+
+// Resolves the escape sequences allowed in string values
+fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => result.push(' '),
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some('\\') => result.push('\\'),
+            // Anything else is left for the `Exec` quoting rules
+            Some(c) => {
+                result.push('\\');
+                result.push(c);
+            }
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+// Splits a `;` separated list, keeping escaped semicolons
+fn split_list(value: &str) -> Vec<String> {
+    let mut items = vec![];
+    let mut current = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(';') => current.push(';'),
+                Some(c) => {
+                    current.push('\\');
+                    current.push(c);
+                }
+                None => current.push('\\'),
+            },
+            ';' => items.push(unescape(&std::mem::take(&mut current))),
+            c => current.push(c),
+        }
+    }
+    items.push(unescape(&current));
+    items.retain(|item| !item.trim().is_empty());
+    items
+}
+
+// The raw value for the first matching locale, lists have to be split before unescaping
+fn localized_value<'a, T: AsRef<str>>(
+    section: &'a AttrSelector<T>,
+    key: &str,
+    locales: &[String],
+) -> Option<&'a str> {
+    locales
+        .iter()
+        .find_map(|locale| section.attr_with_param(key, locale))
+        .or_else(|| section.attr(key))
+}
+
+fn localized<T: AsRef<str>>(section: &AttrSelector<T>, key: &str) -> Option<String> {
+    localized_value(section, key, locales()).map(unescape)
+}
+
+fn parse_bool<T: AsRef<str>>(
+    section: &AttrSelector<T>,
+    key: &str,
+) -> Result<bool, DesktopFileError> {
+    section
+        .attr(key)
+        .map_or(Ok(false), |s| s.trim().parse::<bool>())
+        .map_err(|_| DesktopFileError::InvalidFormat)
+}
+
+// Whether `OnlyShowIn` and `NotShowIn` allow the entry in the current desktop environment
+fn shown_in_desktop(only_show_in: &[String], not_show_in: &[String]) -> bool {
+    let current: Vec<String> = env::var("XDG_CURRENT_DESKTOP")
+        .map(|desktops| {
+            desktops
+                .split(':')
+                .filter(|desktop| !desktop.is_empty())
+                .map(|desktop| desktop.to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    if current.iter().any(|desktop| not_show_in.contains(desktop)) {
+        return false;
+    }
+    only_show_in.is_empty() || current.iter().any(|desktop| only_show_in.contains(desktop))
+}
+
+// Looks up an executable by absolute path or in the login shell's `PATH`
+pub(crate) fn find_executable(name: &str) -> Option<PathBuf> {
+    use std::os::unix::fs::PermissionsExt;
+    let is_executable = |path: &Path| {
+        path.metadata()
+            .map(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+            .unwrap_or(false)
+    };
+    if name.contains('/') {
+        let path = PathBuf::from(name);
+        return is_executable(&path).then_some(path);
+    }
+    env::split_paths(paths().path_env())
+        .map(|dir| dir.join(name))
+        .find(|path| is_executable(path))
+}
+
+// Splits an `Exec` value into arguments and expands its field codes.
+// Files and URLs are never passed, so their field codes are dropped.
+pub(crate) fn parse_exec(
+    exec: &str,
+    name: &str,
+    icon: Option<&str>,
+    path: &Path,
+) -> Option<Vec<String>> {
+    let mut args: Vec<String> = vec![];
+    let mut current: Option<String> = None;
+    let mut quoted = false;
+    let mut chars = exec.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                quoted = false;
+            }
+            '"' => {
+                quoted = true;
+                current.get_or_insert_with(String::new);
+            }
+            '\\' if quoted => current.get_or_insert_with(String::new).push(chars.next()?),
+            c if c.is_whitespace() && !quoted => {
+                if let Some(arg) = current.take() {
+                    args.push(arg);
+                }
+            }
+            c => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quoted {
+        return None;
+    }
+    if let Some(arg) = current.take() {
+        args.push(arg);
+    }
+
+    let mut result = vec![];
+    for arg in args {
+        match arg.as_str() {
+            "%f" | "%F" | "%u" | "%U" | "%d" | "%D" | "%n" | "%N" | "%v" | "%m" => continue,
+            "%i" => {
+                if let Some(icon) = icon {
+                    result.push("--icon".to_string());
+                    result.push(icon.to_string());
+                }
+                continue;
+            }
+            _ => {}
+        }
+        let mut expanded = String::with_capacity(arg.len());
+        let mut chars = arg.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                expanded.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('%') => expanded.push('%'),
+                Some('c') => expanded.push_str(name),
+                Some('k') => expanded.push_str(&path.to_string_lossy()),
+                _ => {}
+            }
+        }
+        result.push(expanded);
+    }
+    (!result.is_empty()).then_some(result)
+}
+
+fn terminal_command() -> Option<Vec<String>> {
+    if let Ok(terminal) = env::var("TERMINAL") {
+        let mut command: Vec<String> = terminal.split_whitespace().map(String::from).collect();
+        if !command.is_empty() {
+            command.push("-e".to_string());
+            return Some(command);
+        }
+    }
+    TERMINALS.iter().find_map(|(terminal, flag)| {
+        find_executable(terminal)?;
+        let mut command = vec![terminal.to_string()];
+        command.extend(flag.map(String::from));
+        Some(command)
+    })
 }
 
 impl ApplicationDesktopFile {
     pub(crate) fn resolve_icon(&self) -> Option<PathBuf> {
         let icon_name = self.icon.as_ref()?;
 
-        lookup(icon_name).with_cache().find()
+        resolve_icon(icon_name)
     }
+    // Starts the application, or one of its actions, detached from Loungy
+    pub(crate) fn launch(&self, action: Option<&str>) -> anyhow::Result<()> {
+        let (exec, icon) = match action {
+            Some(id) => {
+                let action = self
+                    .actions
+                    .iter()
+                    .find(|action| action.id == id)
+                    .ok_or_else(|| anyhow::anyhow!("Unknown desktop action: {}", id))?;
+                (&action.exec, action.icon.as_ref().or(self.icon.as_ref()))
+            }
+            None => (&self.exec, self.icon.as_ref()),
+        };
+        let mut args = parse_exec(exec, &self.name, icon.map(|s| s.as_str()), &self.path)
+            .ok_or_else(|| anyhow::anyhow!("Invalid Exec in {}", self.path.display()))?;
+        if self.terminal {
+            let mut command =
+                terminal_command().ok_or_else(|| anyhow::anyhow!("No terminal emulator found"))?;
+            command.append(&mut args);
+            args = command;
+        }
+
+        let mut command = Command::new(&args[0]);
+        command
+            .args(&args[1..])
+            .env("PATH", paths().path_env())
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        if let Some(dir) = self.working_dir.as_ref().filter(|dir| dir.is_dir()) {
+            command.current_dir(dir);
+        }
+        {
+            // Own process group, so the app survives Loungy and doesn't receive its signals
+            use std::os::unix::process::CommandExt;
+            command.process_group(0);
+        }
+        let mut child = command.spawn()?;
+        std::thread::spawn(move || child.wait());
+        Ok(())
+    }
+}
+
+pub(crate) fn resolve_icon(icon: &str) -> Option<PathBuf> {
+    // Absolute paths are allowed in place of icon names
+    if icon.starts_with('/') {
+        let path = PathBuf::from(icon);
+        return path.exists().then_some(path);
+    }
+    lookup(icon).with_cache().find()
 }
 
 impl TryFrom<&PathBuf> for ApplicationDesktopFile {
     type Error = DesktopFileError;
 
     fn try_from(value: &PathBuf) -> Result<Self, Self::Error> {
+        if !value.exists() {
+            return Err(DesktopFileError::FileNotFound);
+        }
         let entry = parse_entry(value).map_err(|_| DesktopFileError::InvalidFormat)?;
+        if !entry.has_section("Desktop Entry") {
+            return Err(DesktopFileError::NoDesktopEntry);
+        }
 
         let content_section: AttrSelector<&str> = entry.section("Desktop Entry");
-        let name = content_section
-            .attr("Name")
-            .ok_or(DesktopFileError::NoDesktopEntry)?
-            .to_string();
-
-        let icon = content_section.attr("Icon").map(|s| s.to_string());
-
-        let keywords = content_section
-            .attr("Keywords")
-            .map(|s| s.split(';').map(|s| s.to_string()).collect())
-            .unwrap_or_default();
-
-        let no_display = content_section
-            .attr("NoDisplay")
-            .map_or(Ok(false), |s| s.parse::<bool>())
-            .map_err(|_| DesktopFileError::InvalidFormat)?;
+        if content_section
+            .attr("Type")
+            .is_some_and(|kind| kind != "Application")
+        {
+            // Links and directories can't be launched
+            return Err(DesktopFileError::HiddenFile);
+        }
 
-        if no_display {
+        if parse_bool(&content_section, "NoDisplay")? || parse_bool(&content_section, "Hidden")? {
             // Hidden files are typically used for window managers and other system utilities
             // so this is not an application we can start
             return Err(DesktopFileError::HiddenFile);
         }
 
+        let only_show_in = content_section
+            .attr("OnlyShowIn")
+            .map(split_list)
+            .unwrap_or_default();
+        let not_show_in = content_section
+            .attr("NotShowIn")
+            .map(split_list)
+            .unwrap_or_default();
+        if !shown_in_desktop(&only_show_in, &not_show_in) {
+            return Err(DesktopFileError::HiddenFile);
+        }
+
+        if let Some(try_exec) = content_section.attr("TryExec") {
+            if find_executable(&unescape(try_exec)).is_none() {
+                return Err(DesktopFileError::NotInstalled);
+            }
+        }
+
+        let name = localized(&content_section, "Name").ok_or(DesktopFileError::NoDesktopEntry)?;
+        let exec = content_section
+            .attr("Exec")
+            .map(unescape)
+            .ok_or(DesktopFileError::InvalidFormat)?;
+
+        let icon = content_section.attr("Icon").map(unescape);
+
+        let keywords = localized_value(&content_section, "Keywords", locales())
+            .map(split_list)
+            .unwrap_or_default();
+
+        let actions = content_section
+            .attr("Actions")
+            .map(split_list)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|id| {
+                let section = entry.section(format!("Desktop Action {}", id));
+                Some(DesktopAction {
+                    name: localized(&section, "Name")?,
+                    icon: section.attr("Icon").map(unescape),
+                    exec: section.attr("Exec").map(unescape)?,
+                    id,
+                })
+            })
+            .collect();
+
         Ok(ApplicationDesktopFile {
+            path: value.clone(),
             name,
+            generic_name: localized(&content_section, "GenericName"),
+            comment: localized(&content_section, "Comment"),
             icon,
             keywords,
+            exec,
+            working_dir: content_section
+                .attr("Path")
+                .map(|s| PathBuf::from(unescape(s))),
+            terminal: parse_bool(&content_section, "Terminal")?,
+            actions,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use freedesktop_entry_parser::Entry;
+
+    use super::*;
+
+    fn exec(value: &str, icon: Option<&str>) -> Option<Vec<String>> {
+        parse_exec(
+            value,
+            "Editor",
+            icon,
+            Path::new("/usr/share/applications/editor.desktop"),
+        )
+    }
+
+    fn args(values: &[&str]) -> Option<Vec<String>> {
+        Some(values.iter().map(|value| value.to_string()).collect())
+    }
+
+    #[test]
+    fn exec_quoting() {
+        assert_eq!(
+            exec(r#"editor --title "Two  Words" plain"#, None),
+            args(&["editor", "--title", "Two  Words", "plain"])
+        );
+        assert_eq!(
+            exec(r#""/opt/my editor/bin" "say \"hi\"" "a\\b" """#, None),
+            args(&["/opt/my editor/bin", r#"say "hi""#, r"a\b", ""])
+        );
+        assert_eq!(exec(r#"editor "unterminated"#, None), None);
+    }
+
+    #[test]
+    fn exec_field_codes() {
+        for code in ["%f", "%F", "%u", "%U"] {
+            assert_eq!(exec(&format!("editor {}", code), None), args(&["editor"]));
+        }
+        assert_eq!(
+            exec("editor %i", Some("accessories-text-editor")),
+            args(&["editor", "--icon", "accessories-text-editor"])
+        );
+        assert_eq!(exec("editor %i", None), args(&["editor"]));
+        assert_eq!(
+            exec("editor --class=%c %k", None),
+            args(&[
+                "editor",
+                "--class=Editor",
+                "/usr/share/applications/editor.desktop"
+            ])
+        );
+        assert_eq!(
+            exec("editor --zoom=100%%", None),
+            args(&["editor", "--zoom=100%"])
+        );
+        assert_eq!(exec("%U", None), None);
+    }
+
+    #[test]
+    fn list_splitting() {
+        assert_eq!(split_list("a;b;c;"), ["a", "b", "c"]);
+        assert_eq!(split_list("a;; ;b"), ["a", "b"]);
+        assert_eq!(split_list(r"a\;b;c"), ["a;b", "c"]);
+        assert_eq!(split_list(r"a\sb;c"), ["a b", "c"]);
+        // An escaped backslash followed by the separator
+        assert_eq!(split_list(r"a\\;b"), [r"a\", "b"]);
+    }
+
+    #[test]
+    fn locale_variants_most_specific_first() {
+        assert_eq!(
+            locale_variants("de_DE.UTF-8@euro"),
+            ["de_DE@euro", "de_DE", "de@euro", "de"]
+        );
+        assert_eq!(locale_variants("fr_FR.UTF-8"), ["fr_FR", "fr"]);
+        assert_eq!(locale_variants("nl"), ["nl"]);
+        assert!(locale_variants("C").is_empty());
+        assert!(locale_variants("POSIX.UTF-8").is_empty());
+    }
+
+    #[test]
+    fn localized_fallback() {
+        let entry = Entry::parse(
+            "[Desktop Entry]\nName=Files\nName[de]=Dateien\nName[de_AT]=Dateien AT\nKeywords=a\\\\;b;\n",
+        )
+        .unwrap();
+        let section = entry.section("Desktop Entry");
+        let name = |locale: &str| localized_value(&section, "Name", &locale_variants(locale));
+        assert_eq!(name("de_AT.UTF-8"), Some("Dateien AT"));
+        assert_eq!(name("de_DE.UTF-8"), Some("Dateien"));
+        assert_eq!(name("fr_FR.UTF-8"), Some("Files"));
+        assert_eq!(name("C"), Some("Files"));
+        // Unescaped once, after splitting
+        assert_eq!(
+            localized_value(&section, "Keywords", &[]).map(split_list),
+            Some(vec![r"a\".to_string(), "b".to_string()])
+        );
+    }
+}
@@ -9,31 +9,35 @@
  *
  */
 
+mod clipboard;
 mod desktop_file;
 
+pub use clipboard::*;
+
+use clipboard::Session;
+
 use walkdir::WalkDir;
 
 use crate::components::shared::{Icon, Img};
-use crate::paths::paths;
+use crate::paths::{env_dir, paths};
 
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Once;
 use std::{env, fs};
 
-use super::AppData;
+use super::{AppAction, AppData};
 
 pub fn get_application_data(path: &PathBuf) -> Option<AppData> {
-    let cache_dir = paths().cache.join("apps");
-    if !cache_dir.exists() {
-        fs::create_dir_all(cache_dir.clone()).unwrap();
-    }
-    let cache = cache_dir.to_string_lossy().to_string();
-    let last = path.components().last();
-    if last.is_none() {
-        return None;
-    }
-
-    let file_name = last.unwrap().as_os_str().to_string_lossy().to_string();
+    let id = get_application_folders()
+        .iter()
+        .find_map(|folder| desktop_file_id(path, folder))
+        .or_else(|| Some(path.file_name()?.to_string_lossy().to_string()))?;
+    application_data(id, path)
+}
 
+fn application_data(id: String, path: &PathBuf) -> Option<AppData> {
     let file = desktop_file::ApplicationDesktopFile::try_from(path).ok()?;
     let icon_url: Option<PathBuf> = file.resolve_icon();
 
@@ -43,79 +47,308 @@ pub fn get_application_data(path: &PathBuf) -> Option<AppData> {
         Img::default().icon(Icon::AppWindow)
     };
 
+    // Generic names and comments make "browser" find Firefox
+    let mut keywords = file.keywords.clone();
+    keywords.extend(file.generic_name.clone());
+    keywords.extend(file.comment.clone());
+
+    let actions = file
+        .actions
+        .iter()
+        .map(|action| AppAction {
+            id: action.id.clone(),
+            name: action.name.clone(),
+            icon: action
+                .icon
+                .as_ref()
+                .and_then(|icon| desktop_file::resolve_icon(icon))
+                .map(|icon| Img::default().file(icon)),
+        })
+        .collect();
+
     Some(AppData {
-        id: file_name.clone(),
+        id,
         name: file.name.clone(),
         icon: icon_img,
         icon_path: icon_url.unwrap_or_else(|| PathBuf::new()),
-        keywords: file.keywords,
+        keywords,
         tag: "Application".to_string(),
+        actions,
     })
 }
 
-pub fn get_application_folders() -> Vec<PathBuf> {
-    let mut dirs = Vec::new();
-
-    if let Ok(data_home) = env::var("XDG_DATA_HOME") {
-        let data_home = PathBuf::from(data_home);
-        if data_home.exists() {
-            dirs.push(data_home);
-        }
-    } else {
-        let home_dir = PathBuf::from("/home").join(whoami::username());
-        let share_dir = home_dir.join(PathBuf::from("/.local/share"));
-
-        if share_dir.exists() {
-            dirs.push(share_dir);
-        }
-    }
+// Launches the application behind a desktop file, or one of its desktop actions
+pub fn launch_application(path: &PathBuf, action: Option<&str>) -> anyhow::Result<()> {
+    desktop_file::ApplicationDesktopFile::try_from(path)?.launch(action)
+}
 
-    if let Ok(xdg_data_dirs) = env::var("XDG_DATA_DIRS") {
-        dirs.extend(
-            xdg_data_dirs
-                .split(":")
-                .map(|s| PathBuf::from(s))
-                .filter(|d| d.exists()),
-        );
-    } else {
-        let usr_local_share = PathBuf::from("/usr/local/share");
-        let usr_share = PathBuf::from("/usr/share");
+fn data_dirs() -> (PathBuf, Vec<PathBuf>) {
+    let home = env::var_os("HOME").map(PathBuf::from).unwrap_or_default();
+    data_dirs_from(
+        &home,
+        env::var("XDG_DATA_HOME").ok(),
+        env::var("XDG_DATA_DIRS").ok(),
+    )
+}
 
-        if usr_share.exists() {
-            dirs.push(usr_share);
-        }
+// `$XDG_DATA_HOME` and `$XDG_DATA_DIRS` with their defaults, most important first
+fn data_dirs_from(
+    home: &Path,
+    data_home: Option<String>,
+    data_dirs: Option<String>,
+) -> (PathBuf, Vec<PathBuf>) {
+    let absolute = |value: String| {
+        env::split_paths(&value)
+            .filter(|path| path.is_absolute())
+            .collect::<Vec<_>>()
+    };
+    let data_home = data_home
+        .and_then(|value| absolute(value).into_iter().next())
+        .unwrap_or_else(|| home.join(".local/share"));
+    let mut data_dirs = data_dirs
+        .map(absolute)
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| {
+            vec![
+                PathBuf::from("/usr/local/share"),
+                PathBuf::from("/usr/share"),
+            ]
+        });
+    // Flatpak and Snap exports, in case the session didn't add them itself
+    data_dirs.extend([
+        data_home.join("flatpak/exports/share"),
+        PathBuf::from("/var/lib/flatpak/exports/share"),
+        PathBuf::from("/var/lib/snapd/desktop"),
+    ]);
+    (data_home, data_dirs)
+}
 
-        if usr_local_share.exists() {
-            dirs.push(usr_local_share);
+// The `applications` folders of the given data dirs, an entry in an earlier folder shadows the same id further down
+pub fn application_folders(data_home: &Path, data_dirs: &[PathBuf]) -> Vec<PathBuf> {
+    let mut folders: Vec<PathBuf> = vec![];
+    for dir in std::iter::once(data_home).chain(data_dirs.iter().map(|dir| dir.as_path())) {
+        let folder = dir.join("applications");
+        if !folders.contains(&folder) {
+            folders.push(folder);
         }
     }
+    folders
+}
 
-    return dirs;
+pub fn get_application_folders() -> Vec<PathBuf> {
+    let (data_home, data_dirs) = data_dirs();
+    application_folders(&data_home, &data_dirs)
+        .into_iter()
+        .filter(|folder| folder.is_dir())
+        .collect()
 }
 
-pub fn get_application_files() -> Vec<PathBuf> {
-    let dirs = get_application_folders();
+// The desktop file id is the path relative to its `applications` folder, with `/` replaced by `-`
+pub fn desktop_file_id(path: &Path, folder: &Path) -> Option<String> {
+    let relative = path.strip_prefix(folder).ok()?;
+    let parts: Vec<String> = relative
+        .components()
+        .map(|part| part.as_os_str().to_string_lossy().to_string())
+        .collect();
+    (!parts.is_empty()).then(|| parts.join("-"))
+}
 
+// Desktop files by id, keeping only the one from the folder with the highest precedence
+pub fn desktop_files(folders: &[PathBuf]) -> Vec<(String, PathBuf)> {
+    let mut seen = HashSet::new();
     let mut files = Vec::new();
-    for dir in dirs {
-        let walker = WalkDir::new(dir).into_iter();
-        for entry in walker {
-            if let Ok(entry) = entry {
-                if entry
-                    .path()
-                    .extension()
-                    .and_then(|ext| Some(ext == "desktop"))
-                    .unwrap_or(false)
-                {
-                    files.push(entry.path().to_path_buf());
-                }
+    for folder in folders {
+        let mut entries: Vec<PathBuf> = WalkDir::new(folder)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.into_path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "desktop"))
+            .collect();
+        entries.sort();
+        for path in entries {
+            let Some(id) = desktop_file_id(&path, folder) else {
+                continue;
+            };
+            if seen.insert(id.clone()) {
+                files.push((id, path));
             }
         }
     }
+    files
+}
 
-    return files;
+// Desktop files with their data, reusing the ids found while walking the folders
+pub fn get_applications() -> Vec<(PathBuf, AppData)> {
+    desktop_files(&get_application_folders())
+        .into_iter()
+        .filter_map(|(id, path)| {
+            let data = application_data(id, &path)?;
+            Some((path, data))
+        })
+        .collect()
 }
 
+fn xprop(args: &[&str]) -> Option<String> {
+    let output = Command::new("xprop")
+        .args(args)
+        .env("PATH", paths().path_env())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// The application of the focused window, known by its `WM_CLASS` such as `"keepassxc", "KeePassXC"`
 pub fn get_frontmost_application_data() -> Option<AppData> {
-    None
+    if Session::detect() == Session::Wayland {
+        // There is no common protocol for asking about the focused window
+        static WARNED: Once = Once::new();
+        WARNED.call_once(|| {
+            log::warn!(
+                "The focused application is unknown on Wayland, rules by application are ignored"
+            )
+        });
+        return None;
+    }
+    // `_NET_ACTIVE_WINDOW(WINDOW): window id # 0x3a00007`
+    let active = xprop(&["-root", "_NET_ACTIVE_WINDOW"])?;
+    let window = active.split('#').nth(1)?.trim().to_string();
+    if window == "0x0" {
+        return None;
+    }
+    // `WM_CLASS(STRING) = "keepassxc", "KeePassXC"`
+    let class = xprop(&["-id", &window, "WM_CLASS"])?;
+    let values: Vec<String> = class
+        .split_once('=')?
+        .1
+        .split(',')
+        .map(|value| value.trim().trim_matches('"').to_string())
+        .filter(|value| !value.is_empty())
+        .collect();
+    let id = values.first()?.clone();
+    Some(AppData {
+        name: values.get(1).unwrap_or(&id).clone(),
+        id,
+        icon: Img::default().icon(Icon::AppWindow),
+        icon_path: PathBuf::new(),
+        keywords: vec![],
+        tag: "Application".to_string(),
+        actions: vec![],
+    })
+}
+
+// XDG autostart entry, picked up by the desktop session on login
+pub fn set_launch_at_login(enabled: bool) -> anyhow::Result<()> {
+    // The session reads autostart entries from the user's config dir, even for a `LOUNGY_HOME` profile
+    let config = env_dir("XDG_CONFIG_HOME").unwrap_or_else(|| paths().home.join(".config"));
+    let path = config.join("autostart").join("loungy.desktop");
+    if !enabled {
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        return Ok(());
+    }
+    fs::create_dir_all(path.parent().unwrap())?;
+    let exe = env::current_exe()?;
+    fs::write(
+        path,
+        format!(
+            "[Desktop Entry]\nType=Application\nName=Loungy\nExec=\"{}\"\nX-GNOME-Autostart-enabled=true\n",
+            exe.to_string_lossy()
+        ),
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Fresh `$XDG_DATA_HOME` and `$XDG_DATA_DIRS` stand-ins under the temp dir
+    fn fixture(name: &str) -> (PathBuf, PathBuf) {
+        let root = env::temp_dir().join(format!("loungy-xdg-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&root);
+        let (home, system) = (root.join("home"), root.join("system"));
+        fs::create_dir_all(home.join("applications")).unwrap();
+        fs::create_dir_all(system.join("applications")).unwrap();
+        (home, system)
+    }
+
+    fn write(path: PathBuf, content: &str) -> PathBuf {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    const ENTRY: &str = "[Desktop Entry]\nType=Application\nName=Foo\nExec=foo\n";
+
+    #[test]
+    fn application_folders_from_env() {
+        let (home, system) = fixture("env");
+        let (data_home, data_dirs) = data_dirs_from(
+            Path::new("/nonexistent"),
+            Some(home.to_string_lossy().to_string()),
+            Some(format!("relative:{}", system.to_string_lossy())),
+        );
+        let folders = application_folders(&data_home, &data_dirs);
+        assert_eq!(
+            folders[..2],
+            [home.join("applications"), system.join("applications")]
+        );
+        assert!(!folders.contains(&home) && !folders.contains(&system));
+    }
+
+    #[test]
+    fn data_dirs_defaults() {
+        let (data_home, data_dirs) = data_dirs_from(Path::new("/home/foo"), None, Some("".into()));
+        assert_eq!(data_home, PathBuf::from("/home/foo/.local/share"));
+        assert_eq!(
+            data_dirs[..2],
+            [
+                PathBuf::from("/usr/local/share"),
+                PathBuf::from("/usr/share")
+            ]
+        );
+    }
+
+    #[test]
+    fn data_home_shadows_data_dirs() {
+        let (home, system) = fixture("shadow");
+        let user = write(home.join("applications/foo.desktop"), ENTRY);
+        write(system.join("applications/foo.desktop"), ENTRY);
+        write(system.join("applications/bar.desktop"), ENTRY);
+        let files = desktop_files(&application_folders(&home, &[system]));
+        assert_eq!(files.len(), 2);
+        assert!(files.contains(&("foo.desktop".to_string(), user)));
+    }
+
+    #[test]
+    fn nested_desktop_file_id() {
+        let (home, system) = fixture("nested");
+        let path = write(system.join("applications/vendor/foo.desktop"), ENTRY);
+        assert_eq!(
+            desktop_files(&application_folders(&home, &[system])),
+            [("vendor-foo.desktop".to_string(), path)]
+        );
+    }
+
+    #[test]
+    fn hidden_user_file_masks_system_entry() {
+        let (home, system) = fixture("hidden");
+        let user = write(
+            home.join("applications/foo.desktop"),
+            "[Desktop Entry]\nType=Application\nName=Foo\nExec=foo\nHidden=true\n",
+        );
+        write(system.join("applications/foo.desktop"), ENTRY);
+        // The user's file wins by id, and parsing it then drops the application altogether
+        let files = desktop_files(&application_folders(&home, &[system]));
+        assert_eq!(files, [("foo.desktop".to_string(), user.clone())]);
+        assert!(matches!(
+            desktop_file::ApplicationDesktopFile::try_from(&user),
+            Err(desktop_file::DesktopFileError::HiddenFile)
+        ));
+    }
 }
@@ -13,13 +13,16 @@ use crate::components::shared::Img;
 use crate::paths::paths;
 use crate::window::LWindow;
 use cocoa::appkit::NSPasteboard;
+use cocoa::base::nil;
+use cocoa::foundation::{NSArray, NSString};
 use gpui::{App, AsyncApp, Window};
+use std::ffi::CStr;
 use std::time::Duration;
 use std::{
     fs,
     path::{Path, PathBuf},
 };
-use swift_rs::{Bool, SRObject, SRString, swift};
+use swift_rs::{Bool, Int, SRObject, SRString, swift};
 
 use super::{AppData, ClipboardWatcher};
 
@@ -62,14 +65,13 @@ pub fn get_application_data(path: &Path) -> Option<AppData> {
             icon_path,
             keywords: vec![],
             tag: tag.to_string(),
+            actions: vec![],
         }
     })
 }
 
 pub fn get_application_folders() -> Vec<PathBuf> {
-    let user_dir = PathBuf::from("/Users")
-        .join(whoami::username())
-        .join("Applications");
+    let user_dir = paths().home.join("Applications");
     let mut user_dirs = user_dir
         .read_dir()
         .map(|i| {
@@ -143,6 +145,7 @@ pub fn get_frontmost_application_data() -> Option<AppData> {
             icon_path,
             keywords: vec![],
             tag: "".to_string(),
+            actions: vec![],
         }
     })
 }
@@ -179,6 +182,13 @@ pub fn close_and_paste_file(path: &Path, cx: &mut App) {
     .detach();
 }
 
+// Moves the caret of the focused application back by `count` characters
+pub fn move_cursor_left(count: usize) -> anyhow::Result<()> {
+    swift!( fn move_cursor_left(count: Int));
+    unsafe { move_cursor_left(count as Int) };
+    Ok(())
+}
+
 // Function to wait for an input element to be focused and then using AX to fill it
 pub fn autofill(value: &str, password: bool, prev: &str) -> Option<String> {
     unsafe {
@@ -197,9 +207,25 @@ pub fn ocr(path: &Path) {
     unsafe { ocr(SRString::from(path.to_string_lossy().to_string().as_str())) }
 }
 
+// Types currently on the general pasteboard, password managers mark their entries with extra ones
+pub fn clipboard_types() -> Vec<String> {
+    unsafe {
+        let types = NSPasteboard::generalPasteboard(nil).types();
+        if types == nil {
+            return vec![];
+        }
+        (0..types.count())
+            .filter_map(|i| {
+                let ptr = types.objectAtIndex(i).UTF8String();
+                (!ptr.is_null()).then(|| CStr::from_ptr(ptr).to_string_lossy().to_string())
+            })
+            .collect()
+    }
+}
+
 pub async fn clipboard(mut on_change: impl FnMut(&mut AsyncApp), mut cx: AsyncApp) {
     unsafe {
-        let pasteboard = NSPasteboard::generalPasteboard(cocoa::base::nil);
+        let pasteboard = NSPasteboard::generalPasteboard(nil);
         let mut change_count = pasteboard.changeCount();
 
         loop {
@@ -213,3 +239,40 @@ pub async fn clipboard(mut on_change: impl FnMut(&mut AsyncApp), mut cx: AsyncAp
         }
     }
 }
+
+// Per-user launch agent, loaded by launchd on login
+pub fn set_launch_at_login(enabled: bool) -> anyhow::Result<()> {
+    let path = PathBuf::from(std::env::var("HOME")?)
+        .join("Library/LaunchAgents")
+        .join("app.loungy.plist");
+    if !enabled {
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        return Ok(());
+    }
+    fs::create_dir_all(path.parent().unwrap())?;
+    let exe = std::env::current_exe()?;
+    fs::write(
+        path,
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>app.loungy</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{}</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+</dict>
+</plist>
+"#,
+            exe.to_string_lossy()
+        ),
+    )?;
+    Ok(())
+}
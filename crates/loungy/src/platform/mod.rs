@@ -34,6 +34,15 @@ pub struct AppData {
     pub icon_path: PathBuf,
     pub keywords: Vec<String>,
     pub tag: String,
+    // Extra entry points of the application, such as opening a private window
+    pub actions: Vec<AppAction>,
+}
+
+#[derive(Clone)]
+pub struct AppAction {
+    pub id: String,
+    pub name: String,
+    pub icon: Option<Img>,
 }
 
 pub struct ClipboardWatcher {
@@ -58,3 +67,15 @@ impl ClipboardWatcher {
     }
 }
 impl Global for ClipboardWatcher {}
+
+// Application files with their data, which only depends on the file itself here
+#[cfg(not(target_os = "linux"))]
+pub fn get_applications() -> Vec<(PathBuf, AppData)> {
+    get_application_files()
+        .into_iter()
+        .filter_map(|path| {
+            let data = get_application_data(&path)?;
+            Some((path, data))
+        })
+        .collect()
+}
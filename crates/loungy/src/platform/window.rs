@@ -89,6 +89,7 @@ pub fn get_application_data(path: &Path) -> Option<AppData> {
         icon_path,
         keywords: vec![],
         tag: tag.to_string(),
+        actions: vec![],
     })
 }
 pub fn get_application_folders() -> Vec<PathBuf> {
@@ -263,6 +264,7 @@ pub fn get_frontmost_application_data() -> Option<AppData> {
             icon_path,
             keywords: vec![],
             tag: "Foreground".to_string(),
+            actions: vec![],
         })
     }
 }
@@ -473,6 +475,33 @@ pub fn close_and_paste_file(path: &Path, window: &mut Window, cx: &mut App) {
     .detach();
 }
 
+// Moves the caret of the focused application back by `count` characters
+pub fn move_cursor_left(count: usize) -> anyhow::Result<()> {
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        INPUT, INPUT_KEYBOARD, KEYBDINPUT, KEYEVENTF_KEYUP, SendInput, VK_LEFT,
+    };
+
+    let key = |flags| INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: windows::Win32::UI::Input::KeyboardAndMouse::INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: VK_LEFT,
+                wScan: 0,
+                dwFlags: flags,
+                time: 0,
+                dwExtraInfo: 0,
+            },
+        },
+    };
+    let inputs: Vec<INPUT> = (0..count)
+        .flat_map(|_| [key(KEYBD_EVENT_FLAGS(0)), key(KEYEVENTF_KEYUP)])
+        .collect();
+    unsafe {
+        SendInput(&inputs, std::mem::size_of::<INPUT>() as i32);
+    }
+    Ok(())
+}
+
 // Windows的自动填充实现
 pub fn autofill(value: &str, _password: bool, prev: &str) -> Option<String> {
     // Windows实现通常使用UI Automation或SendInput
@@ -563,6 +592,32 @@ pub fn ocr(_path: &Path) {
     println!("OCR功能在Windows上需要额外实现");
 }
 
+// Names of the registered formats currently on the clipboard, password managers mark their entries with extra ones
+pub fn clipboard_types() -> Vec<String> {
+    use windows::Win32::System::DataExchange::{EnumClipboardFormats, GetClipboardFormatNameW};
+
+    unsafe {
+        if OpenClipboard(None).is_err() {
+            return vec![];
+        }
+        let mut types = vec![];
+        let mut format = 0;
+        loop {
+            format = EnumClipboardFormats(format);
+            if format == 0 {
+                break;
+            }
+            let mut name = [0u16; 256];
+            let len = GetClipboardFormatNameW(format, &mut name);
+            if len > 0 {
+                types.push(String::from_utf16_lossy(&name[..len as usize]));
+            }
+        }
+        let _ = CloseClipboard();
+        types
+    }
+}
+
 pub async fn clipboard(mut on_change: impl FnMut(&mut AsyncApp), mut cx: AsyncApp) {
     use windows::Win32::{
         Foundation::{GetLastError, HINSTANCE},
@@ -893,3 +948,31 @@ pub fn run_clipboard_listener() -> Result<(), windows::core::Error> {
         Ok(())
     }
 }
+
+// Registers the executable under the current user's `Run` key
+pub fn set_launch_at_login(enabled: bool) -> anyhow::Result<()> {
+    let key = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+    let mut command = std::process::Command::new("reg");
+    if enabled {
+        let exe = std::env::current_exe()?;
+        command.args([
+            "add",
+            key,
+            "/v",
+            "Loungy",
+            "/t",
+            "REG_SZ",
+            "/d",
+            &format!("\"{}\"", exe.to_string_lossy()),
+            "/f",
+        ]);
+    } else {
+        command.args(["delete", key, "/v", "Loungy", "/f"]);
+    }
+    let status = command.status()?;
+    // Deleting a value that doesn't exist fails, which is fine
+    if !status.success() && enabled {
+        return Err(anyhow::anyhow!("reg exited with {}", status));
+    }
+    Ok(())
+}
@@ -504,6 +504,23 @@ impl StateModel {
         self.pop(cx);
         self.push(view, window, cx);
     }
+    // Shows an error on the active view, for failures that don't happen inside of an action
+    pub fn toast_error(message: impl ToString, cx: &mut App) {
+        if !cx.has_global::<Self>() {
+            log::error!("{}", message.to_string());
+            return;
+        }
+        let toast = cx
+            .global::<Self>()
+            .inner
+            .read(cx)
+            .stack
+            .last()
+            .map(|item| item.actions.read(cx).toast.clone());
+        if let Some(mut toast) = toast {
+            toast.error(message, cx);
+        }
+    }
     pub fn reset(&self, cx: &mut App) {
         self.inner
             .update(cx, |model, _| {
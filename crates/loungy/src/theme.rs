@@ -12,7 +12,7 @@ use gpui::{App, Global, Hsla, Rgba, SharedString, WindowAppearance, WindowBackgr
 use log::error;
 use serde::{Deserialize, Serialize};
 
-use crate::{db::db, paths::paths};
+use crate::{config::config, db::db, paths::paths};
 
 fn color_to_hsla(color: catppuccin::Colour) -> Hsla {
     Rgba {
@@ -178,10 +178,15 @@ impl LTheme {
     }
     pub fn mode(mode: WindowAppearance) -> LTheme {
         let settings = db().get::<ThemeSettings>("theme").unwrap_or_default();
+        let overrides = config().theme.clone();
         let list = LTheme::list();
         let name = match mode {
-            WindowAppearance::Dark | WindowAppearance::VibrantDark => settings.dark,
-            WindowAppearance::Light | WindowAppearance::VibrantLight => settings.light,
+            WindowAppearance::Dark | WindowAppearance::VibrantDark => {
+                overrides.dark.unwrap_or(settings.dark)
+            }
+            WindowAppearance::Light | WindowAppearance::VibrantLight => {
+                overrides.light.unwrap_or(settings.light)
+            }
         };
         list.clone()
             .into_iter()
@@ -61,6 +61,16 @@ final class AutoFill {
         simulatePasteEvent()
     }
 
+    func moveCursorLeft(count: Int) {
+        let sourceRef = CGEventSource(stateID: .combinedSessionState)
+        for _ in 0..<count {
+            CGEvent(keyboardEventSource: sourceRef, virtualKey: CGKeyCode(kVK_LeftArrow), keyDown: true)?
+                .post(tap: .cghidEventTap)
+            CGEvent(keyboardEventSource: sourceRef, virtualKey: CGKeyCode(kVK_LeftArrow), keyDown: false)?
+                .post(tap: .cghidEventTap)
+        }
+    }
+
     func simulatePasteEvent(formatting: Bool = true) {
         let sourceRef = CGEventSource(stateID: .combinedSessionState)
         
@@ -102,6 +102,13 @@ func pasteFile(path: SRString) {
     )
 }
 
+@_cdecl("move_cursor_left")
+func moveCursorLeft(count: Int) {
+    autofill.moveCursorLeft(
+        count: count
+    )
+}
+
 @_cdecl("simulate_paste_event")
 func simulatePasteEvent(formatting: Bool = true) {
     autofill.simulatePasteEvent(